@@ -1,4 +1,4 @@
-use sdp_nom::{attributes::CandidateProtocol, Session};
+use sdp_nom::{attributes::CandidateProtocol, policy::CandidateFilter, Session};
 
 fn read_from_args() -> Option<Session<'static>> {
     if let Some(arg) = std::env::args().nth(1) {
@@ -15,14 +15,11 @@ fn read_from_args() -> Option<Session<'static>> {
 
 fn main() {
     let mut session = read_from_args().unwrap();
-    session.media = session
-        .media
-        .into_iter()
-        .map(|mut media| {
-            media.candidates.retain(|c| c.protocol == CandidateProtocol::Tcp);
-            media
-        })
-        .collect();
+    // drops TCP candidates and keeps all others, like `remove_direct_tcp_candidates_iter`
+    session.filter_candidates(&CandidateFilter {
+        protocols: vec![CandidateProtocol::Tcp],
+        ..Default::default()
+    });
 
     cfg_if::cfg_if! {
         if #[cfg(feature = "serde")] {
@@ -1,4 +1,4 @@
-use sdp_nom::{attributes::CandidateProtocol, sdp_lines, ufmt_to_string};
+use sdp_nom::{attributes::CandidateProtocol, policy::CandidateFilter, sdp_lines, ufmt_to_string};
 
 fn read_from_args() -> Option<String> {
     if let Some(arg) = std::env::args().nth(1) {
@@ -15,10 +15,14 @@ fn read_from_args() -> Option<String> {
 
 fn main() {
     let content = read_from_args().unwrap();
+    let filter = CandidateFilter {
+        protocols: vec![CandidateProtocol::Tcp],
+        ..Default::default()
+    };
     for line in sdp_lines(&content).filter(|line| {
         line.as_attribute()
             .and_then(|a| a.as_candidate())
-            .map(|candidate| candidate.protocol != CandidateProtocol::Tcp)
+            .map(|candidate| !filter.matches(candidate))
             .unwrap_or(true)
     }) {
         cfg_if::cfg_if! {
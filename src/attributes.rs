@@ -5,7 +5,7 @@ use enum_as_inner::EnumAsInner;
 use nom::{
     branch::alt,
     bytes::complete::{is_not, tag},
-    combinator::map,
+    combinator::{all_consuming, map},
     sequence::{preceded, separated_pair, tuple},
     IResult,
 };
@@ -24,12 +24,12 @@ use crate::parsers::*;
 #[cfg(test)]
 use crate::{assert_line, assert_line_print};
 
-pub use bundle::*;
 pub use candidate::*;
 pub use control::*;
 pub use direction::*;
 pub use fingerprint::*;
 pub use fmtp::*;
+pub use group::*;
 pub use ice::*;
 pub use rtcp_option::*;
 pub use rtp::*;
@@ -53,19 +53,26 @@ pub enum AttributeLine<'a> {
     RtpMap(rtpmap::RtpMap<'a>),
     PTime(rtpmap::PTime),
     Ssrc(Ssrc<'a>),
-    BundleGroup(BundleGroup<'a>),
-    SsrcGroup(SsrcGroup),
+    Group(Group<'a>),
+    SsrcGroup(SsrcGroup<'a>),
     Fingerprint(Fingerprint<'a>),
     Direction(Direction),
     Rtp(Rtp<'a>),
-    Rtcp(rtcp::Rtcp),
+    Rtcp(rtcp::Rtcp<'a>),
     Fmtp(Fmtp<'a>),
     RtcpFb(rtcp::Fb<'a>),
+    RtcpXr(rtcp::RtcpXr<'a>),
     RtcpOption(RtcpOption),
     Control(Control<'a>),
     SetupRole(dtls::SetupRole),
+    /// `a=connection:new|existing`
+    DtlsConnection(dtls::ConnectionMode),
+    TlsId(dtls::TlsId<'a>),
     Extmap(extmap::Extmap<'a>),
     BundleOnly,
+    ExtmapAllowMixed,
+    /// `a=cryptex`, [RFC9335](https://tools.ietf.org/html/rfc9335)
+    Cryptex,
     EoC,
     KeyValue {
         key: Cow<'a, str>,
@@ -79,7 +86,7 @@ pub fn attribute_line(input: &str) -> IResult<&str, AttributeLine> {
             map(mid::mid_line, AttributeLine::Mid),
             map(msid::msid_semantic_line, AttributeLine::MsidSemantic),
             map(msid::msid_line, AttributeLine::Msid),
-            map(bundle::bundle_group_line, AttributeLine::BundleGroup),
+            map(group::group_line, AttributeLine::Group),
             map(ice::ice_parameter_line, AttributeLine::Ice),
             map(ssrc::ssrc_line, AttributeLine::Ssrc),
             map(ssrc::ssrc_group_line, AttributeLine::SsrcGroup),
@@ -92,16 +99,26 @@ pub fn attribute_line(input: &str) -> IResult<&str, AttributeLine> {
             map(direction::direction_line, AttributeLine::Direction),
             map(extmap::extmap_line, AttributeLine::Extmap),
             map(dtls::setup_role_line, AttributeLine::SetupRole),
+            map(dtls::connection_mode_line, AttributeLine::DtlsConnection),
+            map(dtls::tls_id_line, AttributeLine::TlsId),
             map(rtp_attribute_line, AttributeLine::Rtp),
             map(rtcp::rtcp_attribute_line, AttributeLine::Rtcp),
             map(fmtp_attribute_line, AttributeLine::Fmtp),
             map(control_attribute_line, AttributeLine::Control),
             map(rtcp::rtcpfb_attribute_line, AttributeLine::RtcpFb),
+            map(rtcp::rtcp_xr_line, AttributeLine::RtcpXr),
             map(rtp_option_line, AttributeLine::RtcpOption),
             map(generic::key_val_attribute_line, |(key, val)| {
                 AttributeLine::KeyValue { key, val }
             }),
             map(tag("a=bundle-only"), |_| AttributeLine::BundleOnly),
+            map(
+                all_consuming(a_line(wsf(tag("extmap-allow-mixed")))),
+                |_| AttributeLine::ExtmapAllowMixed,
+            ),
+            map(all_consuming(a_line(wsf(tag("cryptex")))), |_| {
+                AttributeLine::Cryptex
+            }),
             map(tag("a=end-of-candidates"), |_| AttributeLine::EoC),
             map(generic::key_only_attribute_line, AttributeLine::KeyOnly),
         )),
@@ -112,7 +129,26 @@ pub fn attribute_line(input: &str) -> IResult<&str, AttributeLine> {
 fn test_attribute_line() {
     assert_line_print!(attribute_line, "a=bundle-only");
     assert_line_print!(attribute_line, "a=end-of-candidates");
+    assert_line!(
+        attribute_line,
+        "a=extmap-allow-mixed",
+        AttributeLine::ExtmapAllowMixed,
+        print
+    );
     assert_line_print!(attribute_line, "a=extmap-allowed-mixed");
+    assert_line!(
+        attribute_line,
+        "a=extmap-allow-mixedfoo",
+        AttributeLine::KeyOnly("extmap-allow-mixedfoo".into()),
+        print
+    );
+    assert_line!(attribute_line, "a=cryptex", AttributeLine::Cryptex, print);
+    assert_line!(
+        attribute_line,
+        "a=cryptexfoo",
+        AttributeLine::KeyOnly("cryptexfoo".into()),
+        print
+    );
 }
 
 pub mod generic {
@@ -153,10 +189,65 @@ pub mod generic {
     }
 }
 
-pub mod bundle {
+pub mod group {
     use super::*;
 
+    /// Semantics of an `a=group` line
+    ///
+    ///<https://tools.ietf.org/html/rfc5888#section-8>
+    #[derive(Clone, IntoOwned, PartialEq, Eq)]
+    #[cfg_attr(feature = "debug", derive(Debug))]
+    #[cfg_attr(
+        feature = "serde",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(rename_all = "camelCase")
+    )]
+    #[non_exhaustive]
+    pub enum GroupSemantics<'a> {
+        /// `BUNDLE` <https://tools.ietf.org/html/rfc8843>
+        Bundle,
+        /// `LS` lip synchronization <https://tools.ietf.org/html/rfc5888>
+        LipSync,
+        /// `FID` flow identification <https://tools.ietf.org/html/rfc5888>
+        Fid,
+        /// `FEC-FR` forward error correction <https://tools.ietf.org/html/rfc5956>
+        FecFr,
+        /// `ANAT` alternative network address types <https://tools.ietf.org/html/rfc4091>
+        Anat,
+        /// `DDP` decoding dependency <https://tools.ietf.org/html/rfc5583>
+        Ddp,
+        Other(Cow<'a, str>),
+    }
+
+    impl GroupSemantics<'_> {
+        pub fn as_str(&self) -> &str {
+            match self {
+                GroupSemantics::Bundle => "BUNDLE",
+                GroupSemantics::LipSync => "LS",
+                GroupSemantics::Fid => "FID",
+                GroupSemantics::FecFr => "FEC-FR",
+                GroupSemantics::Anat => "ANAT",
+                GroupSemantics::Ddp => "DDP",
+                GroupSemantics::Other(other) => other.as_ref(),
+            }
+        }
+    }
+
+    pub fn read_group_semantics(input: &str) -> IResult<&str, GroupSemantics> {
+        map(read_string, |semantics| match semantics {
+            "BUNDLE" => GroupSemantics::Bundle,
+            "LS" => GroupSemantics::LipSync,
+            "FID" => GroupSemantics::Fid,
+            "FEC-FR" => GroupSemantics::FecFr,
+            "ANAT" => GroupSemantics::Anat,
+            "DDP" => GroupSemantics::Ddp,
+            other => GroupSemantics::Other(other.into()),
+        })(input)
+    }
+
     /// `a=group:BUNDLE 0 1`
+    ///
+    ///<https://tools.ietf.org/html/rfc5888#section-5>
     #[derive(Clone, IntoOwned, PartialEq, Eq)]
     #[cfg_attr(feature = "debug", derive(Debug))]
     #[cfg_attr(
@@ -164,39 +255,92 @@ pub mod bundle {
         derive(serde::Serialize, serde::Deserialize),
         serde(rename_all = "camelCase")
     )]
-    pub struct BundleGroup<'a>(pub Vec<Cow<'a, str>>);
+    pub struct Group<'a> {
+        pub semantics: GroupSemantics<'a>,
+        pub mids: Vec<Cow<'a, str>>,
+    }
 
-    pub fn bundle_group_line(input: &str) -> IResult<&str, BundleGroup> {
-        attribute("group", bundle_group)(input)
+    impl Group<'_> {
+        pub fn is_bundle(&self) -> bool {
+            self.semantics == GroupSemantics::Bundle
+        }
+
+        pub fn contains(&self, mid: &str) -> bool {
+            self.mids.iter().any(|m| m == mid)
+        }
+    }
+
+    pub fn group_line(input: &str) -> IResult<&str, Group> {
+        attribute("group", group)(input)
     }
 
-    fn bundle_group(input: &str) -> IResult<&str, BundleGroup> {
-        preceded(
-            tag("BUNDLE"),
-            map(wsf(space_separated_cow_strings), BundleGroup),
+    fn group(input: &str) -> IResult<&str, Group> {
+        map(
+            tuple((read_group_semantics, wsf(space_separated_cow_strings))),
+            |(semantics, mids)| Group { semantics, mids },
         )(input)
     }
 
     #[test]
-    fn test_bundle_group_line() {
+    fn test_group_line() {
         assert_line!(
-            bundle_group_line,
+            group_line,
             "a=group:BUNDLE 0 1",
-            BundleGroup(create_test_vec(&["0", "1"])),
+            Group {
+                semantics: GroupSemantics::Bundle,
+                mids: create_test_vec(&["0", "1"])
+            },
             print
         );
         assert_line!(
-            bundle_group_line,
+            group_line,
             "a=group:BUNDLE video",
-            BundleGroup(create_test_vec(&["video"])),
+            Group {
+                semantics: GroupSemantics::Bundle,
+                mids: create_test_vec(&["video"])
+            },
             print
         );
         assert_line!(
-            bundle_group_line,
+            group_line,
             "a=group:BUNDLE sdparta_0 sdparta_1 sdparta_2",
-            BundleGroup(create_test_vec(&["sdparta_0", "sdparta_1", "sdparta_2"])),
+            Group {
+                semantics: GroupSemantics::Bundle,
+                mids: create_test_vec(&["sdparta_0", "sdparta_1", "sdparta_2"])
+            },
+            print
+        );
+        assert_line!(
+            group_line,
+            "a=group:LS 1 2",
+            Group {
+                semantics: GroupSemantics::LipSync,
+                mids: create_test_vec(&["1", "2"])
+            },
             print
         );
+        assert_line!(
+            group_line,
+            "a=group:FEC-FR S1 R1",
+            Group {
+                semantics: GroupSemantics::FecFr,
+                mids: create_test_vec(&["S1", "R1"])
+            },
+            print
+        );
+        assert_line!(
+            group_line,
+            "a=group:SRF 1 2",
+            Group {
+                semantics: GroupSemantics::Other("SRF".into()),
+                mids: create_test_vec(&["1", "2"])
+            },
+            print
+        );
+        assert_line_print!(group_line, "a=group:FID 1 2");
+        assert_line_print!(group_line, "a=group:ANAT 1 2");
+        assert_line_print!(group_line, "a=group:DDP L1 L2");
+        assert_line_print!(group_line, "a=group:BUNDLE");
     }
 }
 
@@ -342,6 +486,42 @@ pub mod direction {
         Inactive,
     }
 
+    impl Direction {
+        /// `true` for `sendrecv` and `sendonly`
+        pub fn sends(self) -> bool {
+            matches!(self, Direction::SendRecv | Direction::SendOnly)
+        }
+
+        /// `true` for `sendrecv` and `recvonly`
+        pub fn receives(self) -> bool {
+            matches!(self, Direction::SendRecv | Direction::RecvOnly)
+        }
+
+        pub(crate) fn from_flags(sends: bool, receives: bool) -> Self {
+            match (sends, receives) {
+                (true, true) => Direction::SendRecv,
+                (true, false) => Direction::SendOnly,
+                (false, true) => Direction::RecvOnly,
+                (false, false) => Direction::Inactive,
+            }
+        }
+
+        /// The direction seen from the other side, what to answer to an offer with this direction.
+        ///
+        /// `sendonly` becomes `recvonly` and vice versa.
+        pub fn reverse(self) -> Self {
+            Self::from_flags(self.receives(), self.sends())
+        }
+
+        /// What is possible with both directions, e.g. `sendrecv` and `recvonly` make `recvonly`.
+        pub fn intersect(self, other: Self) -> Self {
+            Self::from_flags(
+                self.sends() && other.sends(),
+                self.receives() && other.receives(),
+            )
+        }
+    }
+
     pub fn read_direction(input: &str) -> IResult<&str, Direction> {
         alt((
             map(tag("sendrecv"), |_| Direction::SendRecv),
@@ -370,6 +550,22 @@ pub mod direction {
         assert_line!(read_direction, "inactive", Direction::Inactive);
         assert_line!(direction_line, "a=inactive", Direction::Inactive);
     }
+
+    #[test]
+    fn test_direction_negotiation() {
+        use Direction::*;
+        assert_eq!(SendOnly.reverse(), RecvOnly);
+        assert_eq!(RecvOnly.reverse(), SendOnly);
+        assert_eq!(SendRecv.reverse(), SendRecv);
+        assert_eq!(Inactive.reverse(), Inactive);
+
+        assert_eq!(SendRecv.intersect(RecvOnly), RecvOnly);
+        assert_eq!(SendOnly.intersect(RecvOnly), Inactive);
+        assert_eq!(SendRecv.intersect(SendRecv), SendRecv);
+
+        assert!(SendRecv.sends() && SendRecv.receives());
+        assert!(!Inactive.sends() && !Inactive.receives());
+    }
 }
 pub mod rtcp_option {
     use super::*;
@@ -417,8 +613,13 @@ pub mod rtcp_option {
     }
 }
 pub mod fingerprint {
+    //! [RFC8122](https://tools.ietf.org/html/rfc8122#section-5)
     use super::*;
 
+    /// `a=fingerprint:<hash-func> <fingerprint>`
+    ///
+    /// A description may carry several fingerprints, for instance of the same certificate
+    /// with different hash functions or of several certificates.
     #[derive(Clone, IntoOwned, PartialEq, Eq)]
     #[cfg_attr(feature = "debug", derive(Debug))]
     #[cfg_attr(
@@ -431,6 +632,171 @@ pub mod fingerprint {
         pub hash: Cow<'a, str>,
     }
 
+    /// The hash function of a [`Fingerprint`]
+    #[derive(Clone, IntoOwned, PartialEq, Eq)]
+    #[cfg_attr(feature = "debug", derive(Debug))]
+    #[cfg_attr(
+        feature = "serde",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(rename_all = "camelCase")
+    )]
+    #[non_exhaustive]
+    pub enum HashAlgorithm<'a> {
+        Sha1,
+        Sha224,
+        Sha256,
+        Sha384,
+        Sha512,
+        Md5,
+        Md2,
+        Other(Cow<'a, str>),
+    }
+
+    impl<'a> HashAlgorithm<'a> {
+        /// Hash function names are case-insensitive.
+        pub fn from_name(name: &'a str) -> Self {
+            use HashAlgorithm::*;
+            match name.to_ascii_lowercase().as_str() {
+                "sha-1" => Sha1,
+                "sha-224" => Sha224,
+                "sha-256" => Sha256,
+                "sha-384" => Sha384,
+                "sha-512" => Sha512,
+                "md5" => Md5,
+                "md2" => Md2,
+                _ => Other(name.into()),
+            }
+        }
+
+        pub fn as_str(&self) -> &str {
+            use HashAlgorithm::*;
+            match self {
+                Sha1 => "sha-1",
+                Sha224 => "sha-224",
+                Sha256 => "sha-256",
+                Sha384 => "sha-384",
+                Sha512 => "sha-512",
+                Md5 => "md5",
+                Md2 => "md2",
+                Other(name) => name,
+            }
+        }
+
+        /// Length of a digest in bytes, `None` for unknown hash functions.
+        pub fn digest_len(&self) -> Option<usize> {
+            use HashAlgorithm::*;
+            match self {
+                Sha1 => Some(20),
+                Sha224 => Some(28),
+                Sha256 => Some(32),
+                Sha384 => Some(48),
+                Sha512 => Some(64),
+                Md5 | Md2 => Some(16),
+                Other(_) => None,
+            }
+        }
+    }
+
+    impl<'a> Fingerprint<'a> {
+        pub fn new(algorithm: HashAlgorithm, digest: &[u8]) -> Self {
+            let hash = digest
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect::<Vec<_>>()
+                .join(":");
+            Fingerprint {
+                r#type: Cow::Owned(algorithm.as_str().to_owned()),
+                hash: hash.into(),
+            }
+        }
+
+        pub fn algorithm(&self) -> HashAlgorithm<'_> {
+            HashAlgorithm::from_name(&self.r#type)
+        }
+
+        /// Decodes the colon separated hex pairs.
+        ///
+        /// Returns `None` if the hash is malformed or its length doesn't fit the hash function.
+        pub fn decode(&self) -> Option<Vec<u8>> {
+            let digest = self
+                .hash
+                .split(':')
+                .map(|pair| {
+                    if pair.len() == 2 && pair.bytes().all(|b| b.is_ascii_hexdigit()) {
+                        u8::from_str_radix(pair, 16).ok()
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Option<Vec<u8>>>()?;
+            match self.algorithm().digest_len() {
+                Some(len) if len != digest.len() => None,
+                _ => Some(digest),
+            }
+        }
+
+        pub fn is_valid(&self) -> bool {
+            self.decode().is_some()
+        }
+
+        /// Whether `digest` is the value of this fingerprint, compared as bytes.
+        pub fn matches_digest(&self, digest: &[u8]) -> bool {
+            self.decode().is_some_and(|own| own == digest)
+        }
+    }
+
+    #[cfg(feature = "fingerprint_hash")]
+    impl HashAlgorithm<'_> {
+        /// Hashes `data` with one of the SHA hash functions, `None` for any other.
+        pub fn digest(&self, data: &[u8]) -> Option<Vec<u8>> {
+            use sha2::Digest;
+            match self {
+                HashAlgorithm::Sha1 => Some(sha1::Sha1::digest(data).to_vec()),
+                HashAlgorithm::Sha224 => Some(sha2::Sha224::digest(data).to_vec()),
+                HashAlgorithm::Sha256 => Some(sha2::Sha256::digest(data).to_vec()),
+                HashAlgorithm::Sha384 => Some(sha2::Sha384::digest(data).to_vec()),
+                HashAlgorithm::Sha512 => Some(sha2::Sha512::digest(data).to_vec()),
+                _ => None,
+            }
+        }
+    }
+
+    #[cfg(feature = "fingerprint_hash")]
+    impl Fingerprint<'_> {
+        /// The fingerprint of a DER encoded certificate, `None` for hash functions other than SHA.
+        pub fn of_certificate(algorithm: HashAlgorithm, der: &[u8]) -> Option<Self> {
+            let digest = algorithm.digest(der)?;
+            Some(Fingerprint::new(algorithm, &digest))
+        }
+    }
+
+    /// Checks a DER encoded certificate against the fingerprints of a description.
+    ///
+    /// Only fingerprints using one of the SHA hash functions are considered,
+    /// the certificate is accepted if it matches any of them.
+    /// Use [`verify_certificate_with`] for other hash functions.
+    #[cfg(feature = "fingerprint_hash")]
+    pub fn verify_certificate(der: &[u8], fingerprints: &[Fingerprint]) -> bool {
+        verify_certificate_with(der, fingerprints, |algorithm, der| algorithm.digest(der))
+    }
+
+    /// Checks a DER encoded certificate against the fingerprints of a description
+    /// with hash functions supplied by the caller.
+    ///
+    /// `digest` hashes the certificate and returns `None` for unsupported or rejected hash functions.
+    /// Fingerprints with such hash functions are skipped.
+    /// The certificate is accepted if it matches any remaining fingerprint.
+    pub fn verify_certificate_with(
+        der: &[u8],
+        fingerprints: &[Fingerprint],
+        mut digest: impl FnMut(&HashAlgorithm, &[u8]) -> Option<Vec<u8>>,
+    ) -> bool {
+        fingerprints.iter().any(|fingerprint| {
+            let algorithm = fingerprint.algorithm();
+            digest(&algorithm, der).is_some_and(|digest| fingerprint.matches_digest(&digest))
+        })
+    }
+
     /// fingerprint
     pub fn fingerprint_line(input: &str) -> IResult<&str, Fingerprint> {
         attribute("fingerprint", fingerprint)(input)
@@ -453,6 +819,97 @@ pub mod fingerprint {
             fingerprint_line,
             "a=fingerprint:sha-256 19:E2:1C:3B:4B:9F:81:E6:B8:5C:F4:A5:A8:D8:73:04:BB:05:2F:70:9F:04:A9:0E:05:E9:26:33:E8:70:88:A2");
     }
+
+    #[test]
+    fn test_decode() {
+        let (_, sha256) = fingerprint_line("a=fingerprint:SHA-256 19:E2:1C:3B:4B:9F:81:E6:B8:5C:F4:A5:A8:D8:73:04:BB:05:2F:70:9F:04:A9:0E:05:E9:26:33:E8:70:88:a2").unwrap();
+        assert_eq!(sha256.algorithm(), HashAlgorithm::Sha256);
+        let digest = sha256.decode().unwrap();
+        assert_eq!(digest.len(), 32);
+        assert_eq!((digest[0], digest[31]), (0x19, 0xA2));
+        assert_eq!(
+            Fingerprint::new(HashAlgorithm::Sha256, &digest).hash,
+            sha256.hash.to_ascii_uppercase()
+        );
+
+        let (_, sha1) =
+            fingerprint("sha-1 4A:AD:B9:B1:3F:82:18:3B:54:02:12:DF:3E:5D:49:6B:19:E5:7C:AB")
+                .unwrap();
+        assert_eq!(sha1.decode().map(|d| d.len()), Some(20));
+
+        // too short for sha-256
+        let (_, short) = fingerprint("sha-256 4A:AD:B9:B1").unwrap();
+        assert!(!short.is_valid());
+        for malformed in ["sha-1 4A:AD:", "md5 4AAD", "md5 4A:+D", "md5 4A:ADB"] {
+            assert!(fingerprint(malformed).unwrap().1.decode().is_none());
+        }
+        let (_, unknown) = fingerprint("blake3 01:02:03").unwrap();
+        assert_eq!(unknown.algorithm(), HashAlgorithm::Other("blake3".into()));
+        assert_eq!(unknown.decode(), Some(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_verify_certificate_with() {
+        let der = [0x30, 0x82, 0x01, 0x0a];
+        // stands in for a real hash function
+        let digest = |algorithm: &HashAlgorithm, der: &[u8]| {
+            let len = algorithm.digest_len()?;
+            Some(der.iter().copied().cycle().take(len).collect::<Vec<u8>>())
+        };
+        let sha256 = Fingerprint::new(
+            HashAlgorithm::Sha256,
+            &digest(&HashAlgorithm::Sha256, &der).unwrap(),
+        );
+        let other = Fingerprint::new(HashAlgorithm::Sha1, &[0; 20]);
+        let unknown = Fingerprint::new(HashAlgorithm::Other("blake3".into()), &der);
+
+        assert!(verify_certificate_with(
+            &der,
+            &[other.clone(), sha256.clone()],
+            digest
+        ));
+        assert!(!verify_certificate_with(&der, &[other, unknown], digest));
+        assert!(!verify_certificate_with(
+            &der[1..],
+            std::slice::from_ref(&sha256),
+            digest
+        ));
+        assert!(!verify_certificate_with(&der, &[sha256], |_, _| None));
+    }
+
+    #[test]
+    #[cfg(feature = "fingerprint_hash")]
+    fn test_verify_certificate() {
+        // self-signed, fingerprints taken from `openssl x509 -fingerprint`
+        let der = include_bytes!("../fixtures/dtls/certificate.der");
+        let fingerprints = [
+            "sha-1 CE:7C:2F:F1:CC:FA:53:59:84:D9:9F:9C:91:E2:8C:5F:34:29:3F:18",
+            "sha-224 80:43:AA:F9:45:2E:A5:8C:FE:5C:AF:FB:3F:C3:3E:8B:74:53:8B:42:38:5C:B9:D6:45:70:B3:6A",
+            "sha-256 84:B8:08:1D:58:07:27:1C:0A:75:87:2D:29:6A:E0:1F:9E:82:4D:8F:B0:A6:AE:8D:64:9C:D2:64:4F:08:A9:32",
+            "sha-384 B4:78:A4:6D:0B:F8:AA:F4:55:F0:F5:30:CC:AE:0B:13:92:BA:2D:59:62:A0:71:C9:B5:8E:DB:1C:F5:70:50:13:33:D3:3B:2A:71:CE:90:22:96:EA:EF:97:87:B5:B3:6A",
+            "sha-512 35:D4:16:BE:6C:FF:B0:F0:2E:67:DB:14:63:F1:8E:79:EE:5D:1C:5D:DF:37:5E:B1:C3:34:0B:59:2F:8B:B0:20:DA:15:B2:03:CE:15:B6:50:3D:2D:CC:7E:F2:79:E5:AA:11:65:F1:C1:82:15:76:17:78:FA:AD:3A:51:52:52:CE",
+        ]
+        .iter()
+        .map(|line| fingerprint(line).unwrap().1)
+        .collect::<Vec<_>>();
+
+        for expected in &fingerprints {
+            assert!(verify_certificate(der, std::slice::from_ref(expected)));
+            assert_eq!(
+                Fingerprint::of_certificate(expected.algorithm(), der).as_ref(),
+                Some(expected)
+            );
+        }
+
+        let mut tampered = der.to_vec();
+        tampered[100] ^= 1;
+        assert!(!verify_certificate(&tampered, &fingerprints));
+
+        // md5 isn't implemented and is skipped
+        let (_, md5) = fingerprint("md5 00:01:02:03:04:05:06:07:08:09:0A:0B:0C:0D:0E:0F").unwrap();
+        assert!(!verify_certificate(der, std::slice::from_ref(&md5)));
+        assert!(verify_certificate(der, &[md5, fingerprints[2].clone()]));
+    }
 }
 
 pub mod mid {
@@ -485,11 +942,17 @@ pub mod mid {
 }
 
 pub mod msid {
-    use nom::{character::complete::multispace1, combinator::opt};
+    use nom::{
+        character::complete::multispace1,
+        combinator::{all_consuming, opt},
+        multi::many0,
+    };
 
     use super::*;
 
-    /// TODO: type this more strictly, if possible without `Vec`
+    /// `a=msid-semantic: WMS *`
+    ///
+    ///<https://tools.ietf.org/html/draft-ietf-mmusic-msid-16#section-4>
     #[derive(Clone, derive_into_owned::IntoOwned, PartialEq, Eq)]
     #[cfg_attr(feature = "debug", derive(Debug))]
     #[cfg_attr(
@@ -499,7 +962,14 @@ pub mod msid {
     )]
     pub struct MsidSemantic<'a> {
         pub semantic: Cow<'a, str>,
-        pub token: Option<Cow<'a, str>>,
+        /// stream ids, or `*` for all of them
+        pub tokens: Vec<Cow<'a, str>>,
+    }
+
+    impl MsidSemantic<'_> {
+        pub fn is_wildcard(&self) -> bool {
+            self.tokens.iter().any(|token| token == "*")
+        }
     }
 
     pub fn msid_semantic_line(input: &str) -> IResult<&str, MsidSemantic> {
@@ -508,8 +978,11 @@ pub mod msid {
 
     pub fn msid_semantic(input: &str) -> IResult<&str, MsidSemantic> {
         wsf(map(
-            tuple((cowify(read_string), multispace1, opt(cowify(read_string)))),
-            |(semantic, _, token)| MsidSemantic { semantic, token },
+            tuple((
+                cowify(read_string),
+                many0(preceded(multispace1, cowify(read_string))),
+            )),
+            |(semantic, tokens)| MsidSemantic { semantic, tokens },
         ))(input)
     }
 
@@ -520,15 +993,24 @@ pub mod msid {
             "a=msid-semantic: WMS lgsCFqt9kN2fVKw5wg3NKqGdATQoltEwOdMS",
             MsidSemantic {
                 semantic: Cow::Borrowed("WMS"),
-                token: Some(Cow::Borrowed("lgsCFqt9kN2fVKw5wg3NKqGdATQoltEwOdMS"))
+                tokens: vec![Cow::Borrowed("lgsCFqt9kN2fVKw5wg3NKqGdATQoltEwOdMS")]
             }
         );
         assert_line_print!(
             msid_semantic_line,
             "a=msid-semantic: WMS lgsCFqt9kN2fVKw5wg3NKqGdATQoltEwOdMS"
         );
+        assert_line_print!(msid_semantic_line, "a=msid-semantic: WMS");
+        assert_line_print!(msid_semantic_line, "a=msid-semantic: WMS stream streama");
+        assert!(msid_semantic_line("a=msid-semantic: WMS *")
+            .unwrap()
+            .1
+            .is_wildcard());
     }
 
+    /// `a=msid:stream track`
+    ///
+    ///<https://tools.ietf.org/html/rfc8830#section-2>
     #[derive(Clone, IntoOwned, PartialEq, Eq)]
     #[cfg_attr(feature = "debug", derive(Debug))]
     #[cfg_attr(
@@ -536,14 +1018,24 @@ pub mod msid {
         derive(serde::Serialize, serde::Deserialize),
         serde(rename_all = "camelCase")
     )]
-    pub struct Msid<'a>(pub Vec<Cow<'a, str>>);
+    pub struct Msid<'a> {
+        /// `None` for `-`, a track that isn't part of a stream
+        pub stream_id: Option<Cow<'a, str>>,
+        pub track_id: Option<Cow<'a, str>>,
+    }
 
     pub fn msid_line(input: &str) -> IResult<&str, Msid> {
         attribute("msid", msid)(input)
     }
 
     pub fn msid(input: &str) -> IResult<&str, Msid> {
-        wsf(map(space_separated_cow_strings, Msid))(input)
+        all_consuming(wsf(map(
+            tuple((read_string, opt(preceded(multispace1, cowify(read_string))))),
+            |(stream_id, track_id)| Msid {
+                stream_id: Some(stream_id).filter(|id| *id != "-").map(Cow::from),
+                track_id,
+            },
+        )))(input)
     }
 
     #[test]
@@ -551,15 +1043,33 @@ pub mod msid {
         assert_line!(
             msid_line,
             "a=msid:47017fee-b6c1-4162-929c-a25110252400 f83006c5-a0ff-4e0a-9ed9-d3e6747be7d9",
-            Msid(vec![
-                "47017fee-b6c1-4162-929c-a25110252400".into(),
-                "f83006c5-a0ff-4e0a-9ed9-d3e6747be7d9".into()
-            ]),
+            Msid {
+                stream_id: Some("47017fee-b6c1-4162-929c-a25110252400".into()),
+                track_id: Some("f83006c5-a0ff-4e0a-9ed9-d3e6747be7d9".into())
+            },
             print
         );
         assert_line_print!(
             msid_line,
             "a=msid:61317484-2ed4-49d7-9eb7-1414322a7aae f30bdb4a-5db8-49b5-bcdc-e0c9a23172e0"
         );
+        assert_line!(
+            msid_line,
+            "a=msid:- f30bdb4a-5db8-49b5-bcdc-e0c9a23172e0",
+            Msid {
+                stream_id: None,
+                track_id: Some("f30bdb4a-5db8-49b5-bcdc-e0c9a23172e0".into())
+            },
+            print
+        );
+        assert_line!(
+            msid_line,
+            "a=msid:stream",
+            Msid {
+                stream_id: Some("stream".into()),
+                track_id: None
+            },
+            print
+        );
     }
 }
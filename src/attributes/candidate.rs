@@ -5,8 +5,9 @@
 use derive_into_owned::IntoOwned;
 use nom::{
     branch::alt,
-    bytes::complete::tag,
-    combinator::{map, opt},
+    bytes::complete::{tag, take_while1, take_while_m_n},
+    combinator::{map, map_opt},
+    multi::many0,
     sequence::{preceded, tuple},
     IResult,
 };
@@ -27,6 +28,15 @@ pub enum CandidateComponent {
     Rtcp,
 }
 
+impl CandidateComponent {
+    pub fn id(self) -> u32 {
+        match self {
+            CandidateComponent::Rtp => 1,
+            CandidateComponent::Rtcp => 2,
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[cfg_attr(
@@ -56,6 +66,121 @@ pub enum CandidateType {
     Prflx,
 }
 
+impl CandidateType {
+    /// The recommended type preference
+    ///
+    /// <https://tools.ietf.org/html/rfc8445#section-5.1.2.2>
+    pub fn type_preference(self) -> u32 {
+        match self {
+            CandidateType::Host => 126,
+            CandidateType::Prflx => 110,
+            CandidateType::Srflx => 100,
+            CandidateType::Relay => 0,
+        }
+    }
+}
+
+/// `priority = (2^24)*(type preference) + (2^8)*(local preference) + (2^0)*(256 - component ID)`
+///
+/// <https://tools.ietf.org/html/rfc8445#section-5.1.2.1>
+pub fn compute_priority(type_preference: u32, local_preference: u32, component: u32) -> u32 {
+    (type_preference.min(126) << 24)
+        | (local_preference.min(65535) << 8)
+        | (256 - component.clamp(1, 256))
+}
+
+/// The priority of a candidate pair from the priorities of the candidates of the controlling
+/// and the controlled agent.
+///
+/// Candidate priorities are at most 2^31-1, higher values are clamped to that.
+///
+/// <https://tools.ietf.org/html/rfc8445#section-6.1.2.3>
+pub fn pair_priority(controlling: u32, controlled: u32) -> u64 {
+    const MAX_PRIORITY: u32 = (1 << 31) - 1;
+    let (g, d) = (
+        u64::from(controlling.min(MAX_PRIORITY)),
+        u64::from(controlled.min(MAX_PRIORITY)),
+    );
+    (1 << 32) * g.min(d) + 2 * g.max(d) + u64::from(g > d)
+}
+
+/// The address of a candidate, browsers hide host addresses behind mDNS names
+///
+/// <https://tools.ietf.org/html/draft-ietf-mmusic-mdns-ice-candidates-03>
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub enum CandidateAddress<'a> {
+    Ip(IpAddr),
+    /// an FQDN or `.local` mDNS name
+    Fqdn(Cow<'a, str>),
+}
+
+impl CandidateAddress<'_> {
+    pub fn as_ip(&self) -> Option<IpAddr> {
+        match self {
+            CandidateAddress::Ip(ip) => Some(*ip),
+            CandidateAddress::Fqdn(_) => None,
+        }
+    }
+
+    pub fn is_mdns(&self) -> bool {
+        match self {
+            CandidateAddress::Ip(_) => false,
+            CandidateAddress::Fqdn(name) => name.to_ascii_lowercase().ends_with(".local"),
+        }
+    }
+
+    /// Replaces a name with what `lookup` returns for it.
+    fn resolve(&mut self, lookup: &mut impl FnMut(&str) -> Option<IpAddr>) {
+        if let CandidateAddress::Fqdn(name) = self {
+            if let Some(ip) = lookup(name) {
+                *self = CandidateAddress::Ip(ip);
+            }
+        }
+    }
+}
+
+impl From<IpAddr> for CandidateAddress<'_> {
+    fn from(ip: IpAddr) -> Self {
+        CandidateAddress::Ip(ip)
+    }
+}
+
+fn read_hostname(input: &str) -> IResult<&str, &str> {
+    take_while1(|c: char| c.is_ascii_alphanumeric() || c == '-' || c == '.')(input)
+}
+
+pub fn read_candidate_address(input: &str) -> IResult<&str, CandidateAddress> {
+    alt((
+        map(read_addr, CandidateAddress::Ip),
+        map(cowify(read_hostname), CandidateAddress::Fqdn),
+    ))(input)
+}
+
+/// `tcptype` of a TCP candidate
+///
+/// <https://tools.ietf.org/html/rfc6544#section-4.5>
+#[derive(Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub enum TcpType {
+    Active,
+    Passive,
+    /// simultaneous-open
+    So,
+}
+
 /// Candidate
 ///
 /// <https://tools.ietf.org/html/rfc5245#section-15.1>
@@ -73,24 +198,103 @@ pub enum CandidateType {
     serde(rename_all = "camelCase")
 )]
 pub struct Candidate<'a> {
-    pub foundation: u32,
+    /// 1 to 32 ice-chars
+    pub foundation: Cow<'a, str>,
     pub component: CandidateComponent,
     pub protocol: CandidateProtocol,
-    pub priority: u32,         // 2043278322
-    pub addr: IpAddr,          // "192.168.0.56"
-    pub port: u32,             // 44323
-    pub r#type: CandidateType, // "host"
-    pub raddr: Option<IpAddr>, // "192.168.0.56"
-    pub rport: Option<u32>,    // 44323
-    pub tcptype: Option<Cow<'a, str>>,
+    pub priority: u32,                       // 2043278322
+    pub addr: CandidateAddress<'a>, // "192.168.0.56" or "9f3c1a2e-2dc6-4c6c-9f4c-5e2a3c5b0e0a.local"
+    pub port: u32,                  // 44323
+    pub r#type: CandidateType,      // "host"
+    pub raddr: Option<CandidateAddress<'a>>, // "192.168.0.56"
+    pub rport: Option<u32>,         // 44323
+    pub tcptype: Option<TcpType>,
     pub generation: Option<u32>,
+    pub ufrag: Option<Cow<'a, str>>,
     pub network_id: Option<u32>,
+    pub network_cost: Option<u32>,
+    /// unknown extension attributes as name and value, in the order they were given
+    pub extensions: Vec<CandidateExtension<'a>>,
+}
+
+/// An extension attribute of a candidate that isn't known to this crate
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct CandidateExtension<'a> {
+    pub name: Cow<'a, str>,
+    pub value: Cow<'a, str>,
+}
+
+/// foundation = 1*32ice-char
+pub fn read_foundation(input: &str) -> IResult<&str, &str> {
+    take_while_m_n(1, 32, |c: char| {
+        c.is_ascii_alphanumeric() || c == '+' || c == '/'
+    })(input)
+}
+
+pub fn read_tcptype(input: &str) -> IResult<&str, TcpType> {
+    map_opt(read_string, |tcptype| match tcptype {
+        "active" => Some(TcpType::Active),
+        "passive" => Some(TcpType::Passive),
+        "so" => Some(TcpType::So),
+        _ => None,
+    })(input)
+}
+
+enum Extension<'a> {
+    Raddr(CandidateAddress<'a>),
+    Rport(u32),
+    TcpType(TcpType),
+    Generation(u32),
+    Ufrag(Cow<'a, str>),
+    NetworkId(u32),
+    NetworkCost(u32),
+    Other(CandidateExtension<'a>),
+}
+
+fn extension(input: &str) -> IResult<&str, Extension> {
+    alt((
+        map(
+            preceded(wsf(tag("raddr ")), read_candidate_address),
+            Extension::Raddr,
+        ),
+        map(preceded(wsf(tag("rport ")), read_number), Extension::Rport),
+        map(
+            preceded(wsf(tag("tcptype ")), read_tcptype),
+            Extension::TcpType,
+        ),
+        map(
+            preceded(wsf(tag("generation ")), read_number),
+            Extension::Generation,
+        ),
+        map(
+            preceded(wsf(tag("ufrag ")), cowify(read_string)),
+            Extension::Ufrag,
+        ),
+        map(
+            preceded(wsf(tag("network-id ")), read_number),
+            Extension::NetworkId,
+        ),
+        map(
+            preceded(wsf(tag("network-cost ")), read_number),
+            Extension::NetworkCost,
+        ),
+        map(
+            tuple((wsf(cowify(read_string)), cowify(read_string))),
+            |(name, value)| Extension::Other(CandidateExtension { name, value }),
+        ),
+    ))(input)
 }
 
 pub fn candidate(input: &str) -> IResult<&str, Candidate> {
     map(
         tuple((
-            wsf(read_number), // foundation
+            wsf(cowify(read_foundation)), // foundation
             // component:
             wsf(alt((
                 map(tag("1"), |_| CandidateComponent::Rtp),
@@ -102,9 +306,9 @@ pub fn candidate(input: &str) -> IResult<&str, Candidate> {
                 map(alt((tag("TCP"), tag("tcp"))), |_| CandidateProtocol::Tcp),
                 map(alt((tag("DCCP"), tag("dccp"))), |_| CandidateProtocol::Dccp),
             ))),
-            wsf(read_number), // priority
-            wsf(read_addr),   // addr
-            wsf(read_number), // port
+            wsf(read_number),            // priority
+            wsf(read_candidate_address), // addr
+            wsf(read_number),            // port
             preceded(
                 tag("typ"),
                 // typ:
@@ -115,42 +319,105 @@ pub fn candidate(input: &str) -> IResult<&str, Candidate> {
                     map(tag("prflx"), |_| CandidateType::Prflx),
                 ))),
             ),
-            opt(preceded(wsf(tag("raddr")), read_addr)), // raddr
-            opt(preceded(wsf(tag("rport")), read_number)), // rport
-            opt(preceded(wsf(tag("tcptype")), cowify(read_string))), // tcptype
-            opt(preceded(wsf(tag("generation")), read_number)), // generation
-            opt(preceded(wsf(tag("network-id")), read_number)), // generation
+            many0(extension), // extensions, in any order
         )),
-        |(
-            foundation,
-            component,
-            protocol,
-            priority,
-            addr,
-            port,
-            r#type,
-            raddr,
-            rport,
-            tcptype,
-            generation,
-            network_id,
-        )| Candidate {
-            foundation,
-            component,
-            protocol,
-            priority,
-            addr,
-            port,
-            r#type,
-            raddr,
-            rport,
-            tcptype,
-            generation,
-            network_id,
+        |(foundation, component, protocol, priority, addr, port, r#type, extensions)| {
+            let mut candidate = Candidate {
+                foundation,
+                component,
+                protocol,
+                priority,
+                addr,
+                port,
+                r#type,
+                raddr: None,
+                rport: None,
+                tcptype: None,
+                generation: None,
+                ufrag: None,
+                network_id: None,
+                network_cost: None,
+                extensions: Vec::new(),
+            };
+            for extension in extensions {
+                match extension {
+                    Extension::Raddr(raddr) => candidate.raddr = Some(raddr),
+                    Extension::Rport(rport) => candidate.rport = Some(rport),
+                    Extension::TcpType(tcptype) => candidate.tcptype = Some(tcptype),
+                    Extension::Generation(generation) => candidate.generation = Some(generation),
+                    Extension::Ufrag(ufrag) => candidate.ufrag = Some(ufrag),
+                    Extension::NetworkId(network_id) => candidate.network_id = Some(network_id),
+                    Extension::NetworkCost(cost) => candidate.network_cost = Some(cost),
+                    Extension::Other(extension) => candidate.extensions.push(extension),
+                }
+            }
+            candidate
         },
     )(input)
 }
 
+impl Candidate<'_> {
+    /// The priority RFC 8445 recommends for this candidate with the given local preference.
+    pub fn recommended_priority(&self, local_preference: u32) -> u32 {
+        compute_priority(
+            self.r#type.type_preference(),
+            local_preference,
+            self.component.id(),
+        )
+    }
+
+    /// The type preference encoded in the priority.
+    pub fn advertised_type_preference(&self) -> u32 {
+        self.priority >> 24
+    }
+
+    /// Whether the advertised priority could have been computed as RFC 8445 describes.
+    ///
+    /// The priority is positive and the type preference is at most 126,
+    /// the one for host candidates. RFC 8445 wants relayed candidates to have the lowest type preference,
+    /// so a relay candidate has to be below that.
+    /// The local preference can be anything, and so can the lowest byte.
+    /// `256 - component` is only recommended, and agents like Chrome give every component
+    /// of a TCP candidate the priority of component 1.
+    pub fn has_plausible_priority(&self) -> bool {
+        let type_preference = self.advertised_type_preference();
+        let max = match self.r#type {
+            CandidateType::Relay => CandidateType::Host.type_preference() - 1,
+            _ => CandidateType::Host.type_preference(),
+        };
+        self.priority > 0 && type_preference <= max
+    }
+
+    /// The priority of the pair of this local candidate and a remote one.
+    pub fn pair_priority(&self, remote: &Candidate, controlling: bool) -> u64 {
+        if controlling {
+            pair_priority(self.priority, remote.priority)
+        } else {
+            pair_priority(remote.priority, self.priority)
+        }
+    }
+
+    /// Whether the address is an mDNS name, which needs resolving before it can be used.
+    pub fn is_mdns(&self) -> bool {
+        self.addr.is_mdns()
+    }
+
+    /// Substitutes names in `addr` and `raddr` by the addresses `lookup` returns for them.
+    ///
+    /// Names `lookup` doesn't know are left alone.
+    pub fn resolve_addresses(&mut self, mut lookup: impl FnMut(&str) -> Option<IpAddr>) {
+        self.addr.resolve(&mut lookup);
+        if let Some(raddr) = self.raddr.as_mut() {
+            raddr.resolve(&mut lookup);
+        }
+    }
+}
+
+/// a bare `candidate:` string as used for trickle ICE, e.g. in `RTCIceCandidate.candidate`
+pub fn bare_candidate(input: &str) -> IResult<&str, Candidate> {
+    preceded(tag("candidate:"), candidate)(input)
+}
+
 /// "a=Candidate"
 pub fn candidate_line(input: &str) -> IResult<&str, Candidate> {
     attribute("candidate", candidate)(input)
@@ -171,8 +438,8 @@ mod tests {
         assert_line_print!(candidate_line, "a=candidate:3348148302 1 tcp 2113937151 192.0.2.1 56500 typ srflx");
         assert_line_print!(candidate_line, "a=candidate:3348148302 2 tcp 2113937151 192.0.2.1 56500 typ srflx");
         assert_line!(candidate_line, "a=candidate:1 1 TCP 2128609279 10.0.1.1 9 typ host tcptype active", Candidate {
-            foundation: 1, component: CandidateComponent::Rtp, protocol: CandidateProtocol::Tcp, priority: 2128609279, addr: Ipv4Addr::new(10,0,1,1).into(), port: 9,
-            r#type: CandidateType::Host, raddr: None, rport: None, tcptype: Some( Cow::from("active"),), generation: None, network_id: None, });
+            foundation: "1".into(), component: CandidateComponent::Rtp, protocol: CandidateProtocol::Tcp, priority: 2128609279, addr: IpAddr::from(Ipv4Addr::new(10,0,1,1)).into(), port: 9,
+            r#type: CandidateType::Host, raddr: None, rport: None, tcptype: Some(TcpType::Active), generation: None, ufrag: None, network_id: None, network_cost: None, extensions: vec![] });
         assert_line_print!(candidate_line, "a=candidate:2 1 tcp 2124414975 10.0.1.1 8998 typ host tcptype passive");
         assert_line_print!(candidate_line, "a=candidate:3 1 tcp 2120220671 10.0.1.1 8999 typ host tcptype so");
         assert_line_print!(candidate_line, "a=candidate:4 1 tcp 1688207359 192.0.2.3 9 typ srflx raddr 10.0.1.1 rport 9 tcptype active");
@@ -184,6 +451,105 @@ mod tests {
         assert_line_print!(candidate_line, "a=candidate:2791055836 1 udp 2122262783 2001:9e8:b0b:8400:c5e3:8776:82fc:7704 58605 typ host generation 0 network-id 2");
     }
 
+    #[test]
+    fn parses_extensions() {
+        assert_line_print!(candidate_line, "a=candidate:Ab+/9 1 udp 2122260223 192.168.0.196 46243 typ host generation 0 ufrag EsAw network-id 1 network-cost 10");
+        assert_line_print!(candidate_line, "a=candidate:1 1 udp 2122260223 192.168.0.196 46243 typ host generation 0 foo bar baz 1");
+
+        let (_, candidate) = candidate_line("a=candidate:1 1 udp 1518280447 47.61.61.61 36768 typ srflx generation 0 rport 36768 raddr 192.168.0.196 foo bar").unwrap();
+        assert_eq!(candidate.raddr, Some(IpAddr::from(Ipv4Addr::new(192, 168, 0, 196)).into()));
+        assert_eq!(candidate.rport, Some(36768));
+        assert_eq!(candidate.generation, Some(0));
+        assert_eq!(candidate.extensions, [CandidateExtension { name: "foo".into(), value: "bar".into() }]);
+
+        let (_, candidate) = candidate_line("a=candidate:1 1 tcp 1 10.0.1.1 9 typ host tcptype so").unwrap();
+        assert_eq!(candidate.tcptype, Some(TcpType::So));
+
+        let (_, candidate) = candidate_line("a=candidate:1 1 tcp 1 10.0.1.1 9 typ host tcptype sofoo").unwrap();
+        assert_eq!(candidate.tcptype, None);
+        assert_eq!(candidate.extensions, [CandidateExtension { name: "tcptype".into(), value: "sofoo".into() }]);
+
+        assert!(candidate_line("a=candidate:abcdefghijklmnopqrstuvwxyz0123456789 1 udp 1 10.0.1.1 9 typ host").is_err());
+        assert!(candidate_line("a=candidate:a-b 1 udp 1 10.0.1.1 9 typ host").is_err());
+    }
+
+    #[test]
+    fn parses_mdns_candidates() {
+        assert_line_print!(candidate_line, "a=candidate:1 1 udp 2122260223 9f3c1a2e-2dc6-4c6c-9f4c-5e2a3c5b0e0a.local 54321 typ host generation 0");
+        assert_line_print!(candidate_line, "a=candidate:2 1 udp 1686052607 47.61.61.61 36768 typ srflx raddr 9f3c1a2e-2dc6-4c6c-9f4c-5e2a3c5b0e0a.local rport 54321");
+        assert_line_print!(candidate_line, "a=candidate:3 1 udp 25108223 turn.example.com 3478 typ relay raddr 0.0.0.0 rport 0");
+
+        let (_, mut candidate) = candidate_line("a=candidate:2 1 udp 1686052607 47.61.61.61 36768 typ srflx raddr 9f3c1a2e.local rport 54321").unwrap();
+        assert!(!candidate.is_mdns());
+        assert!(candidate.raddr.as_ref().unwrap().is_mdns());
+
+        let (_, mut host) = candidate_line("a=candidate:1 1 udp 2122260223 9f3c1a2e.local 54321 typ host").unwrap();
+        assert!(host.is_mdns());
+        assert_eq!(host.addr.as_ip(), None);
+
+        let local = IpAddr::from(Ipv4Addr::new(192, 168, 0, 196));
+        let lookup = |name: &str| if name == "9f3c1a2e.local" { Some(local) } else { None };
+        host.resolve_addresses(lookup);
+        candidate.resolve_addresses(lookup);
+        assert_eq!(host.addr, CandidateAddress::Ip(local));
+        assert!(!host.is_mdns());
+        assert_eq!(candidate.raddr, Some(CandidateAddress::Ip(local)));
+
+        let (_, mut unknown) = candidate_line("a=candidate:1 1 udp 2122260223 other.local 54321 typ host").unwrap();
+        unknown.resolve_addresses(lookup);
+        assert!(unknown.is_mdns());
+    }
+
+    #[test]
+    fn parses_bare_candidate() {
+        let (_, bare) = bare_candidate("candidate:1467250027 1 udp 2122260223 192.168.0.196 46243 typ host generation 0 ufrag EsAw").unwrap();
+        let (_, line) = candidate_line("a=candidate:1467250027 1 udp 2122260223 192.168.0.196 46243 typ host generation 0 ufrag EsAw").unwrap();
+        assert_eq!(bare, line);
+        assert!(bare_candidate("a=candidate:1467250027 1 udp 2122260223 192.168.0.196 46243 typ host").is_err());
+    }
+
+    #[test]
+    fn computes_priorities() {
+        assert_eq!(compute_priority(126, 65535, 1), 2130706431);
+        assert_eq!(compute_priority(126, 65535, 2), 2130706430);
+        assert_eq!(compute_priority(100, 65535, 1), 1694498815);
+        assert_eq!(compute_priority(0, 65535, 1), 16777215);
+
+        let (_, host) = candidate("1467250027 1 udp 2122260223 192.168.0.196 46243 typ host").unwrap();
+        let (_, relay) = candidate("750991856 1 udp 25108223 237.30.30.30 58779 typ relay").unwrap();
+        assert_eq!(host.recommended_priority(65535), 2130706431);
+        assert_eq!(host.advertised_type_preference(), 126);
+        assert!(host.has_plausible_priority());
+        assert!(relay.has_plausible_priority());
+
+        // Chrome uses the priority of component 1 for both components of a TCP candidate
+        let (_, tcp_component_2) = candidate("435653019 2 tcp 1845501695 192.168.0.196 0 typ host").unwrap();
+        assert!(tcp_component_2.has_plausible_priority());
+        let (_, zero) = candidate("1 1 udp 0 192.168.0.196 46243 typ host").unwrap();
+        assert!(!zero.has_plausible_priority());
+        let (_, too_high) = candidate("1 1 udp 4294967295 192.168.0.196 46243 typ host").unwrap();
+        assert!(!too_high.has_plausible_priority());
+        let (_, relay_as_host) = candidate("1 1 udp 2122260223 237.30.30.30 58779 typ relay").unwrap();
+        assert!(!relay_as_host.has_plausible_priority());
+        let (_, relay_below_host) = candidate("1 1 udp 2105483007 237.30.30.30 58779 typ relay").unwrap();
+        assert!(relay_below_host.has_plausible_priority());
+    }
+
+    #[test]
+    fn computes_pair_priorities() {
+        assert_eq!(pair_priority(2, 1), (1 << 32) + 4 + 1);
+        assert_eq!(pair_priority(1, 2), (1 << 32) + 4);
+        let max = u64::from(u32::MAX >> 1);
+        assert_eq!(pair_priority(u32::MAX, u32::MAX), (1 << 32) * max + 2 * max);
+        assert_eq!(pair_priority(u32::MAX, u32::MAX >> 1), pair_priority(u32::MAX >> 1, u32::MAX));
+
+        let (_, host) = candidate("1 1 udp 2122260223 192.168.0.196 46243 typ host").unwrap();
+        let (_, relay) = candidate("2 1 udp 25108223 237.30.30.30 58779 typ relay").unwrap();
+        assert_eq!(host.pair_priority(&relay, true), pair_priority(2122260223, 25108223));
+        assert_eq!(host.pair_priority(&relay, false), pair_priority(25108223, 2122260223));
+        assert!(host.pair_priority(&relay, true) > host.pair_priority(&relay, false));
+    }
+
     #[test]
     fn audio_lines() {
 
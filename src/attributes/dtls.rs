@@ -1,5 +1,8 @@
-//! <https://tools.ietf.org/html/rfc4572>
+//! <https://tools.ietf.org/html/rfc4572>, <https://tools.ietf.org/html/rfc4145> and <https://tools.ietf.org/html/rfc8842>
 
+use std::borrow::Cow;
+
+use derive_into_owned::IntoOwned;
 use nom::{branch::alt, bytes::complete::tag, combinator::map, IResult};
 
 #[cfg(test)]
@@ -18,6 +21,8 @@ pub enum SetupRole {
     Active,
     Passive,
     ActPass,
+    /// don't set up a connection for now
+    HoldConn,
 }
 
 fn read_setup_role(input: &str) -> IResult<&str, SetupRole> {
@@ -25,6 +30,7 @@ fn read_setup_role(input: &str) -> IResult<&str, SetupRole> {
         map(tag("active"), |_| SetupRole::Active),
         map(tag("passive"), |_| SetupRole::Passive),
         map(tag("actpass"), |_| SetupRole::ActPass),
+        map(tag("holdconn"), |_| SetupRole::HoldConn),
     ))(input)
 }
 
@@ -32,6 +38,7 @@ fn read_setup_role(input: &str) -> IResult<&str, SetupRole> {
 fn test_setup_role() {
     assert_line!(read_setup_role, "active", SetupRole::Active);
     assert_line!(read_setup_role, "passive", SetupRole::Passive);
+    assert_line!(read_setup_role, "holdconn", SetupRole::HoldConn);
 }
 
 pub fn setup_role_line(input: &str) -> IResult<&str, SetupRole> {
@@ -53,4 +60,94 @@ fn test_setup_role_line() {
         SetupRole::Passive,
         print
     );
+    assert_line!(
+        setup_role_line,
+        "a=setup:holdconn",
+        SetupRole::HoldConn,
+        print
+    );
+}
+
+/// `a=connection:new` or `a=connection:existing`
+///<https://tools.ietf.org/html/rfc4145#section-5>
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub enum ConnectionMode {
+    New,
+    Existing,
+}
+
+fn read_connection_mode(input: &str) -> IResult<&str, ConnectionMode> {
+    alt((
+        map(tag("new"), |_| ConnectionMode::New),
+        map(tag("existing"), |_| ConnectionMode::Existing),
+    ))(input)
+}
+
+pub fn connection_mode_line(input: &str) -> IResult<&str, ConnectionMode> {
+    attribute("connection", read_connection_mode)(input)
+}
+
+#[test]
+fn test_connection_mode_line() {
+    assert_line!(
+        connection_mode_line,
+        "a=connection:new",
+        ConnectionMode::New,
+        print
+    );
+    assert_line!(
+        connection_mode_line,
+        "a=connection:existing",
+        ConnectionMode::Existing,
+        print
+    );
+}
+
+/// `a=tls-id`, identifies a DTLS association
+///<https://tools.ietf.org/html/rfc8842#section-4>
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct TlsId<'a>(pub Cow<'a, str>);
+
+impl TlsId<'_> {
+    /// 20 to 255 characters out of `ALPHA / DIGIT / "+" / "/" / "-" / "_"`
+    pub fn is_valid(&self) -> bool {
+        (20..=255).contains(&self.0.len())
+            && self
+                .0
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"+/-_".contains(&b))
+    }
+}
+
+pub fn tls_id_line(input: &str) -> IResult<&str, TlsId> {
+    attribute("tls-id", map(cowify(read_string), TlsId))(input)
+}
+
+#[test]
+fn test_tls_id_line() {
+    assert_line!(
+        tls_id_line,
+        "a=tls-id:abc3de65cddef001be82",
+        TlsId("abc3de65cddef001be82".into()),
+        print
+    );
+    assert!(tls_id_line("a=tls-id:abc3de65cddef001be82")
+        .unwrap()
+        .1
+        .is_valid());
+    assert!(!TlsId("short".into()).is_valid());
+    assert!(!TlsId("abc3de65cddef001be82!".into()).is_valid());
 }
@@ -5,8 +5,9 @@ use std::borrow::Cow;
 use derive_into_owned::IntoOwned;
 use nom::{
     bytes::complete::tag,
+    character::complete::space1,
     combinator::{map, opt},
-    sequence::{preceded, tuple},
+    sequence::{preceded, terminated, tuple},
     IResult,
 };
 
@@ -27,23 +28,117 @@ use crate::parsers::*;
 pub struct Extmap<'a> {
     pub value: u32,
     pub direction: Option<Direction>,
+    /// the URI of the extension, without the `urn:ietf:params:rtp-hdrext:encrypt` wrapper
     pub uri: Cow<'a, str>,
+    /// whether the extension is wrapped in `urn:ietf:params:rtp-hdrext:encrypt`,
+    /// [RFC6904](https://tools.ietf.org/html/rfc6904)
+    pub encrypted: bool,
     pub attributes: Vec<Cow<'a, str>>,
 }
 
+impl Extmap<'_> {
+    pub fn extension(&self) -> RtpHeaderExtension<'_> {
+        RtpHeaderExtension::from_uri(&self.uri)
+    }
+}
+
+/// RTP header extensions commonly negotiated by WebRTC endpoints, identified by their `a=extmap` URI
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub enum RtpHeaderExtension<'a> {
+    /// `http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01`
+    TransportCc,
+    /// `http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time`
+    AbsSendTime,
+    /// `urn:ietf:params:rtp-hdrext:sdes:mid`
+    Mid,
+    /// `urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id`
+    RtpStreamId,
+    /// `urn:ietf:params:rtp-hdrext:sdes:repaired-rtp-stream-id`
+    RepairedRtpStreamId,
+    /// `urn:ietf:params:rtp-hdrext:ssrc-audio-level`
+    AudioLevel,
+    /// `urn:3gpp:video-orientation`
+    VideoOrientation,
+    /// `https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension`
+    DependencyDescriptor,
+    Other(Cow<'a, str>),
+}
+
+impl<'a> RtpHeaderExtension<'a> {
+    pub fn from_uri(uri: &'a str) -> Self {
+        use RtpHeaderExtension::*;
+        match uri {
+            "http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01" => {
+                TransportCc
+            }
+            "http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time" => AbsSendTime,
+            "urn:ietf:params:rtp-hdrext:sdes:mid" => Mid,
+            "urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id" => RtpStreamId,
+            "urn:ietf:params:rtp-hdrext:sdes:repaired-rtp-stream-id" => RepairedRtpStreamId,
+            "urn:ietf:params:rtp-hdrext:ssrc-audio-level" => AudioLevel,
+            "urn:3gpp:video-orientation" => VideoOrientation,
+            "https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension" => {
+                DependencyDescriptor
+            }
+            other => Other(other.into()),
+        }
+    }
+
+    pub fn uri(&self) -> &str {
+        use RtpHeaderExtension::*;
+        match self {
+            TransportCc => "http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01",
+            AbsSendTime => "http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time",
+            Mid => "urn:ietf:params:rtp-hdrext:sdes:mid",
+            RtpStreamId => "urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id",
+            RepairedRtpStreamId => "urn:ietf:params:rtp-hdrext:sdes:repaired-rtp-stream-id",
+            AudioLevel => "urn:ietf:params:rtp-hdrext:ssrc-audio-level",
+            VideoOrientation => "urn:3gpp:video-orientation",
+            DependencyDescriptor => {
+                "https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension"
+            }
+            Other(uri) => uri,
+        }
+    }
+
+    pub(crate) fn into_uri(self) -> Cow<'a, str> {
+        match self {
+            RtpHeaderExtension::Other(uri) => uri,
+            known => Cow::Owned(known.uri().to_owned()),
+        }
+    }
+}
+
+/// The URI that marks an extension as encrypted with [RFC6904](https://tools.ietf.org/html/rfc6904)
+pub const ENCRYPT_URI: &str = "urn:ietf:params:rtp-hdrext:encrypt";
+
+/// IDs usable with the one-byte header
+pub const ONE_BYTE_IDS: std::ops::RangeInclusive<u32> = 1..=14;
+/// IDs that require the two-byte header
+pub const TWO_BYTE_IDS: std::ops::RangeInclusive<u32> = 15..=255;
+
 /// a=extmap:<value>["/"<direction>] <URI> <extensionattributes>
 fn read_extmap(input: &str) -> IResult<&str, Extmap> {
     map(
         tuple((
             wsf(read_number),                             // <value>
             wsf(opt(preceded(tag("/"), read_direction))), // ["/"<direction>]
+            opt(terminated(tag(ENCRYPT_URI), space1)),    // [encrypt]
             wsf(cowify(read_string)),                     // <uri>
             wsf(read_as_cow_strings),                     // <extensionattributes>
         )),
-        |(value, direction, uri, attributes)| Extmap {
+        |(value, direction, encrypt, uri, attributes)| Extmap {
             value,
             direction,
             uri,
+            encrypted: encrypt.is_some(),
             attributes,
         },
     )(input)
@@ -62,6 +157,7 @@ fn test_extmap() {
             value: 1,
             direction: Some(Direction::SendOnly),
             uri: "URI-toffset".into(),
+            encrypted: false,
             attributes: vec![]
         }
     );
@@ -72,6 +168,7 @@ fn test_extmap() {
             value: 2,
             direction: None,
             uri: "urn:ietf:params:rtp-hdrext:toffset".into(),
+            encrypted: false,
             attributes: vec![]
         }
     );
@@ -81,11 +178,9 @@ fn test_extmap() {
         Extmap {
             value: 3,
             direction: None,
-            uri: "urn:ietf:params:rtp-hdrext:encrypt".into(),
-            attributes: vec![
-                "urn:ietf:params:rtp-hdrext:smpte-tc".into(),
-                "25@600/24".into()
-            ]
+            uri: "urn:ietf:params:rtp-hdrext:smpte-tc".into(),
+            encrypted: true,
+            attributes: vec!["25@600/24".into()]
         }
     );
     assert_line!(
@@ -94,8 +189,9 @@ fn test_extmap() {
         Extmap {
             value: 4,
             direction: Some(Direction::RecvOnly),
-            uri: "urn:ietf:params:rtp-hdrext:encrypt".into(),
-            attributes: vec!["URI-gps-string".into()]
+            uri: "URI-gps-string".into(),
+            encrypted: true,
+            attributes: vec![]
         }
     );
 }
@@ -109,10 +205,53 @@ fn test_extmap_line() {
     );
     assert_line!(
         extmap_line,
-        "a=extmap:4/recvonly urn:ietf:params:rtp-hdrext:encrypt URI-gps-string"
+        "a=extmap:4/recvonly urn:ietf:params:rtp-hdrext:encrypt URI-gps-string",
+        Extmap {
+            value: 4,
+            direction: Some(Direction::RecvOnly),
+            uri: "URI-gps-string".into(),
+            encrypted: true,
+            attributes: vec![]
+        },
+        print
+    );
+    assert_line!(
+        extmap_line,
+        "a=extmap:5 urn:ietf:params:rtp-hdrext:encrypt",
+        Extmap {
+            value: 5,
+            direction: None,
+            uri: "urn:ietf:params:rtp-hdrext:encrypt".into(),
+            encrypted: false,
+            attributes: vec![]
+        },
+        print
     );
     assert_line!(
         extmap_line,
         "a=extmap:2/sendrecv http://example.com/082005/ext.htm#xmeta short"
     );
 }
+
+#[test]
+fn test_known_extensions() {
+    for uri in [
+        "http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01",
+        "http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time",
+        "urn:ietf:params:rtp-hdrext:sdes:mid",
+        "urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id",
+        "urn:ietf:params:rtp-hdrext:sdes:repaired-rtp-stream-id",
+        "urn:ietf:params:rtp-hdrext:ssrc-audio-level",
+        "urn:3gpp:video-orientation",
+        "https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension",
+        "urn:ietf:params:rtp-hdrext:toffset",
+    ] {
+        assert_eq!(RtpHeaderExtension::from_uri(uri).uri(), uri);
+    }
+    let (_, extmap) = read_extmap("9 urn:ietf:params:rtp-hdrext:sdes:mid").unwrap();
+    assert_eq!(extmap.extension(), RtpHeaderExtension::Mid);
+    assert_eq!(
+        RtpHeaderExtension::from_uri("urn:ietf:params:rtp-hdrext:toffset"),
+        RtpHeaderExtension::Other("urn:ietf:params:rtp-hdrext:toffset".into())
+    );
+}
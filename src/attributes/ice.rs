@@ -1,11 +1,16 @@
 use std::borrow::Cow;
 
 use derive_into_owned::IntoOwned;
-use nom::{branch::alt, bytes::complete::tag, combinator::map, IResult};
+use nom::{
+    branch::alt, bytes::complete::tag, combinator::map, multi::many1, sequence::tuple, IResult,
+};
 
 #[cfg(test)]
 use crate::assert_line;
-use crate::parsers::*;
+use crate::{
+    attributes::candidate::{read_candidate_address, CandidateAddress},
+    parsers::*,
+};
 
 #[derive(Clone, IntoOwned, PartialEq, Eq)]
 #[cfg_attr(feature = "debug", derive(Debug))]
@@ -18,12 +23,123 @@ use crate::parsers::*;
 pub enum IceParameter<'a> {
     Ufrag(Cow<'a, str>),
     Pwd(Cow<'a, str>),
-    Options(Cow<'a, str>),
+    Options(IceOptions<'a>),
     Mismatch,
     Lite,
+    RemoteCandidates(Vec<RemoteCandidate<'a>>),
+    /// `a=ice-pacing` in milliseconds
+    Pacing(u32),
 }
 
-#[derive(Default, IntoOwned, PartialEq, Eq)]
+/// A token of `a=ice-options`
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub enum IceOption<'a> {
+    /// [RFC8840](https://tools.ietf.org/html/rfc8840)
+    Trickle,
+    /// [RFC8445](https://tools.ietf.org/html/rfc8445)
+    Ice2,
+    /// [draft-thatcher-ice-renomination](https://tools.ietf.org/html/draft-thatcher-ice-renomination)
+    Renomination,
+    Other(Cow<'a, str>),
+}
+
+impl IceOption<'_> {
+    pub fn as_str(&self) -> &str {
+        match self {
+            IceOption::Trickle => "trickle",
+            IceOption::Ice2 => "ice2",
+            IceOption::Renomination => "renomination",
+            IceOption::Other(other) => other,
+        }
+    }
+}
+
+pub fn read_ice_option(input: &str) -> IResult<&str, IceOption> {
+    map(read_string, |option| match option {
+        "trickle" => IceOption::Trickle,
+        "ice2" => IceOption::Ice2,
+        "renomination" => IceOption::Renomination,
+        other => IceOption::Other(other.into()),
+    })(input)
+}
+
+/// `a=ice-options:trickle ice2`
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct IceOptions<'a>(pub Vec<IceOption<'a>>);
+
+impl<'a> IceOptions<'a> {
+    pub fn contains(&self, option: &IceOption) -> bool {
+        self.0.contains(option)
+    }
+
+    pub fn trickle(&self) -> bool {
+        self.contains(&IceOption::Trickle)
+    }
+
+    pub fn ice2(&self) -> bool {
+        self.contains(&IceOption::Ice2)
+    }
+
+    pub fn renomination(&self) -> bool {
+        self.contains(&IceOption::Renomination)
+    }
+}
+
+impl<'a> From<Vec<IceOption<'a>>> for IceOptions<'a> {
+    fn from(options: Vec<IceOption<'a>>) -> Self {
+        IceOptions(options)
+    }
+}
+
+pub fn read_ice_options(input: &str) -> IResult<&str, IceOptions> {
+    map(many1(wsf(read_ice_option)), IceOptions)(input)
+}
+
+/// A candidate of `a=remote-candidates`
+///
+/// read [RFC8839 Section 5.2](https://tools.ietf.org/html/rfc8839#section-5.2)
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct RemoteCandidate<'a> {
+    pub component: u32,
+    pub addr: CandidateAddress<'a>,
+    pub port: u32,
+}
+
+pub fn read_remote_candidate(input: &str) -> IResult<&str, RemoteCandidate> {
+    map(
+        tuple((
+            wsf(read_number),
+            wsf(read_candidate_address),
+            wsf(read_number),
+        )),
+        |(component, addr, port)| RemoteCandidate {
+            component,
+            addr,
+            port,
+        },
+    )(input)
+}
+
+#[derive(Clone, Default, IntoOwned, PartialEq, Eq)]
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[cfg_attr(
     feature = "serde",
@@ -33,19 +149,50 @@ pub enum IceParameter<'a> {
 pub struct Ice<'a> {
     pub ufrag: Option<Cow<'a, str>>,
     pub pwd: Option<Cow<'a, str>>,
-    pub options: Option<Cow<'a, str>>,
+    pub options: Option<IceOptions<'a>>,
+}
+
+/// `ice-char = ALPHA / DIGIT / "+" / "/"`
+fn is_ice_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '+' || c == '/'
+}
+
+/// Whether `ufrag` has 4 to 256 `ice-char`s as required by [RFC8839 Section 5.4](https://tools.ietf.org/html/rfc8839#section-5.4).
+pub fn is_valid_ufrag(ufrag: &str) -> bool {
+    (4..=256).contains(&ufrag.len()) && ufrag.chars().all(is_ice_char)
+}
+
+/// Whether `pwd` has 22 to 256 `ice-char`s as required by [RFC8839 Section 5.4](https://tools.ietf.org/html/rfc8839#section-5.4).
+pub fn is_valid_pwd(pwd: &str) -> bool {
+    (22..=256).contains(&pwd.len()) && pwd.chars().all(is_ice_char)
+}
+
+impl Ice<'_> {
+    /// Whether `ufrag` and `pwd` are present and valid.
+    ///
+    /// Parsing is lenient, this is meant for checking descriptions before using them.
+    pub fn is_valid(&self) -> bool {
+        self.ufrag.as_deref().is_some_and(is_valid_ufrag)
+            && self.pwd.as_deref().is_some_and(is_valid_pwd)
+    }
+
+    pub fn has_option(&self, option: &IceOption) -> bool {
+        self.options.as_ref().is_some_and(|o| o.contains(option))
+    }
 }
 
 pub fn ice_parameter_line(input: &str) -> IResult<&str, IceParameter> {
     alt((
         attribute("ice-ufrag", map(cowify(read_string), IceParameter::Ufrag)),
         attribute("ice-pwd", map(cowify(read_string), IceParameter::Pwd)),
-        attribute(
-            "ice-options",
-            map(cowify(read_string), IceParameter::Options),
-        ),
+        attribute("ice-options", map(read_ice_options, IceParameter::Options)),
         a_line(map(tag("ice-mismatch"), |_| IceParameter::Mismatch)),
         a_line(map(tag("ice-lite"), |_| IceParameter::Lite)),
+        attribute(
+            "remote-candidates",
+            map(many1(read_remote_candidate), IceParameter::RemoteCandidates),
+        ),
+        attribute("ice-pacing", map(wsf(read_number), IceParameter::Pacing)),
     ))(input)
 }
 
@@ -78,8 +225,80 @@ fn test_ice_parameters() {
     assert_line!(
         ice_parameter_line,
         "a=ice-options:trickle",
-        IceParameter::Options("trickle".into()),
+        IceParameter::Options(vec![IceOption::Trickle].into()),
         print
     );
     assert_line!(ice_parameter_line, "a=ice-lite", IceParameter::Lite, print);
+    assert_line!(
+        ice_parameter_line,
+        "a=ice-options:trickle ice2 renomination google-ice",
+        IceParameter::Options(
+            vec![
+                IceOption::Trickle,
+                IceOption::Ice2,
+                IceOption::Renomination,
+                IceOption::Other("google-ice".into())
+            ]
+            .into()
+        ),
+        print
+    );
+    assert_line!(
+        ice_parameter_line,
+        "a=ice-pacing:50",
+        IceParameter::Pacing(50),
+        print
+    );
+}
+
+#[test]
+fn test_remote_candidates() {
+    assert_line!(
+        ice_parameter_line,
+        "a=remote-candidates:1 192.0.2.3 45664 2 192.0.2.3 45665",
+        IceParameter::RemoteCandidates(vec![
+            RemoteCandidate {
+                component: 1,
+                addr: CandidateAddress::Ip("192.0.2.3".parse().unwrap()),
+                port: 45664,
+            },
+            RemoteCandidate {
+                component: 2,
+                addr: CandidateAddress::Ip("192.0.2.3".parse().unwrap()),
+                port: 45665,
+            },
+        ]),
+        print
+    );
+    assert_line!(
+        ice_parameter_line,
+        "a=remote-candidates:1 9f3c1a2e.local 54321",
+        IceParameter::RemoteCandidates(vec![RemoteCandidate {
+            component: 1,
+            addr: CandidateAddress::Fqdn("9f3c1a2e.local".into()),
+            port: 54321,
+        }]),
+        print
+    );
+}
+
+#[test]
+fn test_ice_validation() {
+    assert!(is_valid_ufrag("x+m/"));
+    assert!(!is_valid_ufrag("xm/"));
+    assert!(!is_valid_ufrag("x-m/"));
+    assert!(is_valid_pwd("T0teqPLNQQOf+5W+ls+P2p16"));
+    assert!(!is_valid_pwd("T0teqPLNQQOf+5W+ls+P2"));
+    assert!(!is_valid_pwd(&"a".repeat(257)));
+
+    let mut ice = Ice {
+        ufrag: Some("Oyef7uvBlwafI3hT".into()),
+        pwd: Some("T0teqPLNQQOf+5W+ls+P2p16".into()),
+        options: None,
+    };
+    assert!(ice.is_valid());
+    ice.pwd = Some("short".into());
+    assert!(!ice.is_valid());
+    ice.pwd = None;
+    assert!(!ice.is_valid());
 }
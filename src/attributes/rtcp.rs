@@ -4,16 +4,21 @@ use derive_into_owned::IntoOwned;
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    combinator::{map, opt},
+    combinator::{all_consuming, map, opt},
+    multi::{many0, separated_list1},
     sequence::{preceded, tuple},
     IResult,
 };
 
-use std::{borrow::Cow, net::IpAddr};
+use std::borrow::Cow;
 
-use crate::parsers::*;
 #[cfg(test)]
 use crate::{assert_line, assert_line_print};
+use crate::{
+    attributes::candidate::{read_candidate_address, CandidateAddress},
+    lines::connection::Connection,
+    parsers::*,
+};
 
 #[derive(Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "debug", derive(Debug))]
@@ -34,35 +39,59 @@ pub fn read_net_type(input: &str) -> IResult<&str, NetType> {
 /// Rtcp
 ///
 ///<https://tools.ietf.org/html/rfc3605>
-/// `a=rtcp:65179 IN IP4 10.23.34.567`
-#[derive(Clone, PartialEq, Eq)]
+/// `a=rtcp:65179 IN IP4 10.23.34.567` or just `a=rtcp:9`
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[cfg_attr(
     feature = "serde",
     derive(serde::Serialize, serde::Deserialize),
     serde(rename_all = "camelCase")
 )]
-pub struct Rtcp {
+pub struct Rtcp<'a> {
     pub port: u32,
+    pub address: Option<RtcpAddress<'a>>,
+}
+
+/// The optional address part of `a=rtcp`, which may also be a hostname
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct RtcpAddress<'a> {
     pub net_type: NetType,
     pub ip_ver: IpVer,
-    pub addr: IpAddr,
+    pub addr: CandidateAddress<'a>,
+}
+
+impl<'a> Rtcp<'a> {
+    /// Where RTCP is sent to: the address of `a=rtcp`, or the one of `c=` if it has none.
+    pub fn transport_address(
+        &self,
+        connection: Option<&Connection>,
+    ) -> Option<(CandidateAddress<'a>, u32)> {
+        let addr = match &self.address {
+            Some(address) => address.addr.clone(),
+            None => connection?.addr.into(),
+        };
+        Some((addr, self.port))
+    }
 }
 
 pub fn rtcp_attribute_line(input: &str) -> IResult<&str, Rtcp> {
     attribute("rtcp", rtcp_attribute)(input)
 }
 
-fn rtcp_attribute(input: &str) -> IResult<&str, Rtcp> {
+fn read_rtcp_address(input: &str) -> IResult<&str, RtcpAddress> {
     map(
         tuple((
-            wsf(read_number),   // port
-            wsf(read_net_type), // net_type
-            wsf(read_ipver),    // ip_ver
-            wsf(read_addr),     // addr
+            wsf(read_net_type),          // net_type
+            wsf(read_ipver),             // ip_ver
+            wsf(read_candidate_address), // addr
         )),
-        |(port, net_type, ip_ver, addr)| Rtcp {
-            port,
+        |(net_type, ip_ver, addr)| RtcpAddress {
             net_type,
             ip_ver,
             addr,
@@ -70,10 +99,63 @@ fn rtcp_attribute(input: &str) -> IResult<&str, Rtcp> {
     )(input)
 }
 
+fn rtcp_attribute(input: &str) -> IResult<&str, Rtcp> {
+    map(
+        tuple((
+            wsf(read_number),            // port
+            opt(wsf(read_rtcp_address)), // address
+        )),
+        |(port, address)| Rtcp { port, address },
+    )(input)
+}
+
 #[test]
 fn test_rtcp_attribute_line() {
     assert_line_print!(rtcp_attribute_line, "a=rtcp:65179 IN IP4 10.23.34.255");
     assert_line_print!(rtcp_attribute_line, "a=rtcp:65179 IN IP4 ::1");
+    assert_line!(
+        rtcp_attribute_line,
+        "a=rtcp:9",
+        Rtcp {
+            port: 9,
+            address: None
+        },
+        print
+    );
+    assert_line!(
+        rtcp_attribute_line,
+        "a=rtcp:53020 IN IP4 rtcp.example.com",
+        Rtcp {
+            port: 53020,
+            address: Some(RtcpAddress {
+                net_type: NetType::IN,
+                ip_ver: IpVer::Ip4,
+                addr: CandidateAddress::Fqdn("rtcp.example.com".into())
+            })
+        },
+        print
+    );
+}
+
+#[test]
+fn test_rtcp_transport_address() {
+    let connection = Connection {
+        ip_ver: IpVer::Ip4,
+        addr: "203.0.113.1".parse().unwrap(),
+        mask: None,
+    };
+    let (_, rtcp) = rtcp_attribute("9").unwrap();
+    assert_eq!(
+        rtcp.transport_address(Some(&connection)),
+        Some((CandidateAddress::Ip(connection.addr), 9))
+    );
+    assert_eq!(rtcp.transport_address(None), None);
+
+    let (_, rtcp) = rtcp_attribute("53020 IN IP4 198.51.100.1").unwrap();
+    assert_eq!(
+        rtcp.transport_address(Some(&connection)),
+        Some((CandidateAddress::Ip("198.51.100.1".parse().unwrap()), 53020))
+    );
 }
 
 // ///////////////////////
@@ -93,10 +175,76 @@ fn test_rtcp_attribute_line() {
     serde(rename_all = "camelCase")
 )]
 pub struct Fb<'a> {
-    pub payload: u32,
+    pub payload: FbPayload,
     pub val: FbVal<'a>,
 }
 
+/// The payload type of `a=rtcp-fb`, `*` applies to all payload types of the media section
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub enum FbPayload {
+    Wildcard,
+    Payload(u32),
+}
+
+impl FbPayload {
+    /// Whether feedback with this payload applies to `payload`, which the wildcard always does.
+    pub fn applies_to(self, payload: u32) -> bool {
+        match self {
+            FbPayload::Wildcard => true,
+            FbPayload::Payload(p) => p == payload,
+        }
+    }
+}
+
+impl From<u32> for FbPayload {
+    fn from(payload: u32) -> Self {
+        FbPayload::Payload(payload)
+    }
+}
+
+impl PartialEq<u32> for FbPayload {
+    fn eq(&self, payload: &u32) -> bool {
+        *self == FbPayload::Payload(*payload)
+    }
+}
+
+fn read_fb_payload(input: &str) -> IResult<&str, FbPayload> {
+    alt((
+        map(tag("*"), |_| FbPayload::Wildcard),
+        map(read_number, FbPayload::Payload),
+    ))(input)
+}
+
+/// The feedback from `offered` for `payload` that is also in `supported`, as it would be answered.
+///
+/// Wildcard feedback is answered for `payload` explicitly, duplicates are dropped.
+pub fn intersect_feedback<'a>(
+    offered: &[Fb<'a>],
+    payload: u32,
+    supported: &[FbVal],
+) -> Vec<Fb<'a>> {
+    let mut answered: Vec<Fb<'a>> = Vec::new();
+    for fb in offered {
+        if fb.payload.applies_to(payload)
+            && supported.contains(&fb.val)
+            && !answered.iter().any(|a| a.val == fb.val)
+        {
+            answered.push(Fb {
+                payload: payload.into(),
+                val: fb.val.clone(),
+            });
+        }
+    }
+    answered
+}
+
 #[derive(Clone, IntoOwned, PartialEq, Eq)]
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[cfg_attr(
@@ -107,8 +255,15 @@ pub struct Fb<'a> {
 #[non_exhaustive]
 pub enum FbVal<'a> {
     Ack(FbAckParam<'a>),
-    Nack(FbNackParam<'a>),
+    /// `nack` without a parameter is the generic NACK
+    Nack(Option<FbNackParam<'a>>),
     TrrInt(u32),
+    /// [RFC5104](https://tools.ietf.org/html/rfc5104#section-7.1)
+    Ccm(FbCcmParam),
+    /// [draft-alvestrand-rmcat-remb](https://tools.ietf.org/html/draft-alvestrand-rmcat-remb)
+    GoogRemb,
+    /// [draft-holmer-rmcat-transport-wide-cc-extensions](https://tools.ietf.org/html/draft-holmer-rmcat-transport-wide-cc-extensions-01)
+    TransportCc,
     RtcpFbId {
         id: Cow<'a, str>,
         param: Option<FbParam<'a>>,
@@ -201,6 +356,8 @@ pub enum FbNackParam<'a> {
 
 fn read_nack_param(input: &str) -> IResult<&str, FbNackParam> {
     alt((
+        map(tag("pli"), |_| FbNackParam::Pli),
+        map(tag("sli"), |_| FbNackParam::Sli),
         map(tag("rpsi"), |_| FbNackParam::Rpsi),
         map(
             preceded(tag("app"), wsf(cowify(read_string))),
@@ -213,11 +370,56 @@ fn read_nack_param(input: &str) -> IResult<&str, FbNackParam> {
     ))(input)
 }
 
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub enum FbCcmParam {
+    Fir,
+    Tmmbr {
+        smaxpr: Option<u32>,
+    },
+    Tstr,
+    /// the H.271 sub-message types
+    Vbcm(Vec<u32>),
+}
+
+fn read_ccm_param(input: &str) -> IResult<&str, FbCcmParam> {
+    alt((
+        map(tag("fir"), |_| FbCcmParam::Fir),
+        map(
+            preceded(
+                tag("tmmbr"),
+                opt(preceded(wsf(tag("smaxpr=")), read_number)),
+            ),
+            |smaxpr| FbCcmParam::Tmmbr { smaxpr },
+        ),
+        map(tag("tstr"), |_| FbCcmParam::Tstr),
+        map(
+            preceded(tag("vbcm"), many0(wsf(read_number))),
+            FbCcmParam::Vbcm,
+        ),
+    ))(input)
+}
+
 fn read_val(input: &str) -> IResult<&str, FbVal> {
     alt((
         map(preceded(tag("ack"), wsf(read_ack_param)), FbVal::Ack),
-        map(preceded(tag("nack"), wsf(read_nack_param)), FbVal::Nack),
+        map(preceded(tag("nack"), wsf(read_nack_param)), |param| {
+            FbVal::Nack(Some(param))
+        }),
+        map(all_consuming(wsf(tag("nack"))), |_| FbVal::Nack(None)),
         map(preceded(tag("trr-int"), wsf(read_number)), FbVal::TrrInt),
+        map(
+            preceded(tag("ccm"), all_consuming(wsf(read_ccm_param))),
+            FbVal::Ccm,
+        ),
+        map(tag("goog-remb"), |_| FbVal::GoogRemb),
+        map(tag("transport-cc"), |_| FbVal::TransportCc),
         map(
             tuple((wsf(cowify(read_string)), opt(wsf(read_param)))),
             |(id, param)| FbVal::RtcpFbId { id, param },
@@ -231,10 +433,20 @@ fn test_read_val() {
     assert_line!(read_val, "trr-int 100", FbVal::TrrInt(100), print);
     assert_line!(read_val, "ack sli", FbVal::Ack(FbAckParam::Sli(None)), print);
     assert_line!(read_val, "ack sli 5432", FbVal::Ack(FbAckParam::Sli(Some("5432".into()))), print);
-    assert_line!(read_val, "nack rpsi", FbVal::Nack(FbNackParam::Rpsi), print);
-    assert_line!(read_val, "goog-remb", FbVal:: RtcpFbId{id: "goog-remb".into(), param: None}, print);
+    assert_line!(read_val, "nack rpsi", FbVal::Nack(Some(FbNackParam::Rpsi)), print);
+    assert_line!(read_val, "nack pli", FbVal::Nack(Some(FbNackParam::Pli)), print);
+    assert_line!(read_val, "nack sli", FbVal::Nack(Some(FbNackParam::Sli)), print);
+    assert_line!(read_val, "nack", FbVal::Nack(None), print);
+    assert_line!(read_val, "nack foo", FbVal:: RtcpFbId{id: "nack".into(), param: Some(FbParam::Single("foo".into()))}, print);
+    assert_line!(read_val, "goog-remb", FbVal::GoogRemb, print);
+    assert_line!(read_val, "transport-cc", FbVal::TransportCc, print);
     assert_line!(read_val, "ccm", FbVal:: RtcpFbId{id: "ccm".into(), param: None}, print);
-    assert_line!(read_val, "ccm fir", FbVal:: RtcpFbId{id: "ccm".into(), param: Some(FbParam::Single("fir".into()))}, print);
+    assert_line!(read_val, "ccm fir", FbVal::Ccm(FbCcmParam::Fir), print);
+    assert_line!(read_val, "ccm tmmbr", FbVal::Ccm(FbCcmParam::Tmmbr { smaxpr: None }), print);
+    assert_line!(read_val, "ccm tmmbr smaxpr=120", FbVal::Ccm(FbCcmParam::Tmmbr { smaxpr: Some(120) }), print);
+    assert_line!(read_val, "ccm tstr", FbVal::Ccm(FbCcmParam::Tstr), print);
+    assert_line!(read_val, "ccm vbcm 1 2", FbVal::Ccm(FbCcmParam::Vbcm(vec![1, 2])), print);
+    assert_line!(read_val, "ccm foo", FbVal:: RtcpFbId{id: "ccm".into(), param: Some(FbParam::Single("foo".into()))}, print);
     assert_line!(read_val, "fb foo bar", FbVal:: RtcpFbId{id: "fb".into(), param: Some(FbParam::Pair("foo".into(), "bar".into()))}, print);
 }
 
@@ -245,8 +457,8 @@ pub fn rtcpfb_attribute_line(input: &str) -> IResult<&str, Fb> {
 fn rtcpfb_attribute(input: &str) -> IResult<&str, Fb> {
     map(
         tuple((
-            read_number,   // payload
-            wsf(read_val), // val
+            read_fb_payload, // payload
+            wsf(read_val),   // val
         )),
         |(payload, val)| Fb { payload, val },
     )(input)
@@ -258,19 +470,333 @@ fn test_rtcpfb_line() {
     assert_line_print!(rtcpfb_attribute_line, "a=rtcp-fb:98 trr-int 100");
     assert_line_print!(rtcpfb_attribute_line, "a=rtcp-fb:98 ack sli");
     assert_line_print!(rtcpfb_attribute_line, "a=rtcp-fb:98 ack sli 5432");
-    assert_line!(rtcpfb_attribute_line, "a=rtcp-fb:98 nack rpsi", Fb {payload: 98, val: FbVal::Nack(FbNackParam::Rpsi)}, print);
+    assert_line!(rtcpfb_attribute_line, "a=rtcp-fb:98 nack rpsi", Fb {payload: 98.into(), val: FbVal::Nack(Some(FbNackParam::Rpsi))}, print);
 
-    assert_line!(rtcpfb_attribute_line, "a=rtcp-fb:96 goog-remb", Fb {payload: 96, val: FbVal::RtcpFbId{id: "goog-remb".into(), param: None}}, print);
-    assert_line!(rtcpfb_attribute_line, "a=rtcp-fb:96 transport-cc", Fb {payload: 96, val: FbVal::RtcpFbId{id: "transport-cc".into(), param: None}}, print);
-    assert_line!(
-        rtcpfb_attribute_line,
-        "a=rtcp-fb:96 ccm fir",
+    assert_line!(rtcpfb_attribute_line, "a=rtcp-fb:96 goog-remb", Fb {payload: 96.into(), val: FbVal::GoogRemb}, print);
+    assert_line!(rtcpfb_attribute_line, "a=rtcp-fb:96 transport-cc", Fb {payload: 96.into(), val: FbVal::TransportCc}, print);
+    assert_line!(rtcpfb_attribute_line, "a=rtcp-fb:96 ccm fir", Fb {payload: 96.into(), val: FbVal::Ccm(FbCcmParam::Fir)}, print);
+    assert_line!(rtcpfb_attribute_line, "a=rtcp-fb:* nack", Fb {payload: FbPayload::Wildcard, val: FbVal::Nack(None)}, print);
+}
+
+#[test]
+fn test_intersect_feedback() {
+    let offered = [
+        Fb {
+            payload: FbPayload::Wildcard,
+            val: FbVal::Nack(None),
+        },
         Fb {
-            payload: 96,
-            val: FbVal::RtcpFbId{
-                id: "ccm".into(),
-                param: Some(FbParam::Single("fir".into()))
+            payload: 96.into(),
+            val: FbVal::Nack(None),
+        },
+        Fb {
+            payload: 96.into(),
+            val: FbVal::Nack(Some(FbNackParam::Pli)),
+        },
+        Fb {
+            payload: 96.into(),
+            val: FbVal::GoogRemb,
+        },
+        Fb {
+            payload: 97.into(),
+            val: FbVal::TransportCc,
+        },
+    ];
+    let supported = [FbVal::Nack(None), FbVal::TransportCc, FbVal::GoogRemb];
+    assert_eq!(
+        intersect_feedback(&offered, 96, &supported),
+        [
+            Fb {
+                payload: 96.into(),
+                val: FbVal::Nack(None)
+            },
+            Fb {
+                payload: 96.into(),
+                val: FbVal::GoogRemb
+            },
+        ]
+    );
+    assert_eq!(
+        intersect_feedback(&offered, 97, &supported),
+        [
+            Fb {
+                payload: 97.into(),
+                val: FbVal::Nack(None)
+            },
+            Fb {
+                payload: 97.into(),
+                val: FbVal::TransportCc
+            },
+        ]
+    );
+}
+
+// ///////////////////////
+
+/// RTCP Extended Reports
+///
+///<https://tools.ietf.org/html/rfc3611#section-5.1>
+/// eg `a=rtcp-xr:rcvr-rtt=all:10000 stat-summary=loss,dup,jitt voip-metrics`
+#[derive(Clone, Default, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct RtcpXr<'a> {
+    pub formats: Vec<XrFormat<'a>>,
+}
+
+/// A report block of `a=rtcp-xr`, `max_size` limits the size of the block in bytes
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub enum XrFormat<'a> {
+    PktLossRle(Option<u32>),
+    PktDupRle(Option<u32>),
+    PktRcptTimes(Option<u32>),
+    RcvrRtt {
+        mode: RcvrRttMode,
+        max_size: Option<u32>,
+    },
+    /// no flags means none were specified
+    StatSummary(Vec<StatFlag>),
+    VoipMetrics,
+    Other(Cow<'a, str>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub enum RcvrRttMode {
+    All,
+    Sender,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub enum StatFlag {
+    Loss,
+    Dup,
+    Jitt,
+    Ttl,
+    Hl,
+}
+
+fn read_max_size(input: &str) -> IResult<&str, Option<u32>> {
+    opt(preceded(tag("="), read_number))(input)
+}
+
+fn read_stat_flag(input: &str) -> IResult<&str, StatFlag> {
+    alt((
+        map(tag("loss"), |_| StatFlag::Loss),
+        map(tag("dup"), |_| StatFlag::Dup),
+        map(tag("jitt"), |_| StatFlag::Jitt),
+        map(tag("TTL"), |_| StatFlag::Ttl),
+        map(tag("HL"), |_| StatFlag::Hl),
+    ))(input)
+}
+
+fn read_known_xr_format(input: &str) -> IResult<&str, XrFormat> {
+    alt((
+        map(
+            preceded(tag("pkt-loss-rle"), read_max_size),
+            XrFormat::PktLossRle,
+        ),
+        map(
+            preceded(tag("pkt-dup-rle"), read_max_size),
+            XrFormat::PktDupRle,
+        ),
+        map(
+            preceded(tag("pkt-rcpt-times"), read_max_size),
+            XrFormat::PktRcptTimes,
+        ),
+        map(
+            tuple((
+                preceded(
+                    tag("rcvr-rtt="),
+                    alt((
+                        map(tag("all"), |_| RcvrRttMode::All),
+                        map(tag("sender"), |_| RcvrRttMode::Sender),
+                    )),
+                ),
+                opt(preceded(tag(":"), read_number)),
+            )),
+            |(mode, max_size)| XrFormat::RcvrRtt { mode, max_size },
+        ),
+        map(
+            preceded(
+                tag("stat-summary"),
+                opt(preceded(
+                    tag("="),
+                    separated_list1(tag(","), read_stat_flag),
+                )),
+            ),
+            |flags| XrFormat::StatSummary(flags.unwrap_or_default()),
+        ),
+        map(tag("voip-metrics"), |_| XrFormat::VoipMetrics),
+    ))(input)
+}
+
+fn read_xr_format(input: &str) -> IResult<&str, XrFormat> {
+    map(read_string, |format| {
+        all_consuming(read_known_xr_format)(format)
+            .map(|(_, known)| known)
+            .unwrap_or_else(|_| XrFormat::Other(format.into()))
+    })(input)
+}
+
+pub fn rtcp_xr_line(input: &str) -> IResult<&str, RtcpXr> {
+    alt((
+        attribute(
+            "rtcp-xr",
+            map(many0(wsf(read_xr_format)), |formats| RtcpXr { formats }),
+        ),
+        map(a_line(tag("rtcp-xr")), |_| RtcpXr::default()),
+    ))(input)
+}
+
+fn min_size(a: Option<u32>, b: Option<u32>) -> Option<u32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+impl<'a> XrFormat<'a> {
+    fn intersect(&self, other: &XrFormat) -> Option<XrFormat<'a>> {
+        use XrFormat::*;
+        Some(match (self, other) {
+            (PktLossRle(a), PktLossRle(b)) => PktLossRle(min_size(*a, *b)),
+            (PktDupRle(a), PktDupRle(b)) => PktDupRle(min_size(*a, *b)),
+            (PktRcptTimes(a), PktRcptTimes(b)) => PktRcptTimes(min_size(*a, *b)),
+            (
+                RcvrRtt { mode, max_size: a },
+                RcvrRtt {
+                    mode: other_mode,
+                    max_size: b,
+                },
+            ) if mode == other_mode => RcvrRtt {
+                mode: *mode,
+                max_size: min_size(*a, *b),
+            },
+            (StatSummary(a), StatSummary(b)) if a.is_empty() || b.is_empty() => {
+                StatSummary(if a.is_empty() { b.clone() } else { a.clone() })
             }
-        }, print
+            (StatSummary(a), StatSummary(b)) => {
+                let flags = a
+                    .iter()
+                    .filter(|f| b.contains(f))
+                    .copied()
+                    .collect::<Vec<_>>();
+                if flags.is_empty() {
+                    return None;
+                }
+                StatSummary(flags)
+            }
+            (VoipMetrics, VoipMetrics) => VoipMetrics,
+            (Other(a), Other(b)) if a == b => Other(a.clone()),
+            _ => return None,
+        })
+    }
+}
+
+impl<'a> RtcpXr<'a> {
+    /// The report blocks of an offer that are also `supported`, as they would be answered.
+    ///
+    /// Size limits are the smaller of both, `stat-summary` keeps the flags both support.
+    /// Returns `None` if there are no common report blocks.
+    pub fn intersect(&self, supported: &RtcpXr) -> Option<RtcpXr<'a>> {
+        let formats = self
+            .formats
+            .iter()
+            .filter_map(|offered| {
+                supported
+                    .formats
+                    .iter()
+                    .find_map(|format| offered.intersect(format))
+            })
+            .collect::<Vec<_>>();
+        if formats.is_empty() {
+            None
+        } else {
+            Some(RtcpXr { formats })
+        }
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_rtcp_xr_line() {
+    assert_line!(
+        rtcp_xr_line,
+        "a=rtcp-xr:rcvr-rtt=all:10000 stat-summary=loss,dup,jitt voip-metrics",
+        RtcpXr {
+            formats: vec![
+                XrFormat::RcvrRtt { mode: RcvrRttMode::All, max_size: Some(10000) },
+                XrFormat::StatSummary(vec![StatFlag::Loss, StatFlag::Dup, StatFlag::Jitt]),
+                XrFormat::VoipMetrics,
+            ]
+        },
+        print
+    );
+    assert_line!(
+        rtcp_xr_line,
+        "a=rtcp-xr:pkt-loss-rle pkt-dup-rle=100 pkt-rcpt-times=200 rcvr-rtt=sender stat-summary=TTL,HL",
+        RtcpXr {
+            formats: vec![
+                XrFormat::PktLossRle(None),
+                XrFormat::PktDupRle(Some(100)),
+                XrFormat::PktRcptTimes(Some(200)),
+                XrFormat::RcvrRtt { mode: RcvrRttMode::Sender, max_size: None },
+                XrFormat::StatSummary(vec![StatFlag::Ttl, StatFlag::Hl]),
+            ]
+        },
+        print
     );
+    assert_line!(
+        rtcp_xr_line,
+        "a=rtcp-xr:stat-summary rcvr-rtt=never x-custom",
+        RtcpXr {
+            formats: vec![
+                XrFormat::StatSummary(vec![]),
+                XrFormat::Other("rcvr-rtt=never".into()),
+                XrFormat::Other("x-custom".into()),
+            ]
+        },
+        print
+    );
+    assert_line!(rtcp_xr_line, "a=rtcp-xr", RtcpXr::default(), print);
+}
+
+#[test]
+fn test_rtcp_xr_intersect() {
+    let (_, offered) = rtcp_xr_line(
+        "a=rtcp-xr:pkt-loss-rle=400 rcvr-rtt=all:10000 stat-summary=loss,dup,jitt voip-metrics",
+    )
+    .unwrap();
+    let (_, supported) =
+        rtcp_xr_line("a=rtcp-xr:pkt-loss-rle rcvr-rtt=sender stat-summary=jitt,loss,TTL").unwrap();
+    let (_, expected) = rtcp_xr_line("a=rtcp-xr:pkt-loss-rle=400 stat-summary=loss,jitt").unwrap();
+    assert_eq!(offered.intersect(&supported), Some(expected));
+
+    let (_, unsupported) = rtcp_xr_line("a=rtcp-xr:stat-summary=HL").unwrap();
+    assert_eq!(offered.intersect(&unsupported), None);
 }
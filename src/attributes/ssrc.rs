@@ -4,16 +4,59 @@ use derive_into_owned::IntoOwned;
 use nom::{
     branch::alt,
     bytes::complete::{is_not, tag},
-    character::complete::multispace0,
-    combinator::map,
-    sequence::{preceded, separated_pair, tuple},
+    character::complete::{multispace0, space1},
+    combinator::{all_consuming, map, opt},
+    multi::many1,
+    sequence::{preceded, separated_pair, terminated, tuple},
     IResult,
 };
 
+use super::msid::{msid, Msid};
 use crate::parsers::*;
 #[cfg(test)]
 use crate::{assert_line, assert_line_print};
 
+/// The `attribute:value` part of an `a=ssrc` line
+///
+///<https://tools.ietf.org/html/rfc5576#section-6>
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub enum SsrcAttribute<'a> {
+    /// `cname:EocUG1f0fcg/yvY7`
+    Cname(Cow<'a, str>),
+    /// `msid:stream track`, as used by Plan B
+    Msid(Msid<'a>),
+    /// `mslabel:stream`, legacy Chrome
+    MsLabel(Cow<'a, str>),
+    /// `label:track`, legacy Chrome
+    Label(Cow<'a, str>),
+    /// `previous-ssrc:1 2` <https://tools.ietf.org/html/rfc5576#section-6.2>
+    PreviousSsrc(Vec<u32>),
+    Other {
+        attribute: Cow<'a, str>,
+        value: Cow<'a, str>,
+    },
+}
+
+impl SsrcAttribute<'_> {
+    pub fn name(&self) -> &str {
+        match self {
+            SsrcAttribute::Cname(_) => "cname",
+            SsrcAttribute::Msid(_) => "msid",
+            SsrcAttribute::MsLabel(_) => "mslabel",
+            SsrcAttribute::Label(_) => "label",
+            SsrcAttribute::PreviousSsrc(_) => "previous-ssrc",
+            SsrcAttribute::Other { attribute, .. } => attribute.as_ref(),
+        }
+    }
+}
+
 #[derive(Clone, IntoOwned, PartialEq, Eq)]
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[cfg_attr(
@@ -22,9 +65,47 @@ use crate::{assert_line, assert_line_print};
     serde(rename_all = "camelCase")
 )]
 pub struct Ssrc<'a> {
-    pub id: u64,
-    pub attribute: Cow<'a, str>,
-    pub value: Cow<'a, str>,
+    pub id: u32,
+    pub attribute: SsrcAttribute<'a>,
+}
+
+/// SSRCs are 32 bit, anything bigger is rejected
+pub fn read_ssrc_id(input: &str) -> IResult<&str, u32> {
+    read_number(input)
+}
+
+fn read_ssrc_ids(input: &str) -> IResult<&str, Vec<u32>> {
+    many1(terminated(read_ssrc_id, opt(space1)))(input)
+}
+
+pub fn ssrc_attribute(input: &str) -> IResult<&str, SsrcAttribute> {
+    alt((
+        map(
+            preceded(tag("cname:"), cowify(wsf(is_not("\n")))),
+            SsrcAttribute::Cname,
+        ),
+        map(preceded(tag("msid:"), msid), SsrcAttribute::Msid),
+        map(
+            preceded(tag("mslabel:"), cowify(wsf(is_not("\n")))),
+            SsrcAttribute::MsLabel,
+        ),
+        map(
+            preceded(tag("label:"), cowify(wsf(is_not("\n")))),
+            SsrcAttribute::Label,
+        ),
+        map(
+            preceded(tag("previous-ssrc:"), all_consuming(wsf(read_ssrc_ids))),
+            SsrcAttribute::PreviousSsrc,
+        ),
+        map(
+            separated_pair(
+                cowify(read_non_colon_string),
+                tag(":"),
+                cowify(wsf(is_not("\n"))),
+            ),
+            |(attribute, value)| SsrcAttribute::Other { attribute, value },
+        ),
+    ))(input)
 }
 
 /// ssrc
@@ -33,21 +114,10 @@ pub fn ssrc_line(input: &str) -> IResult<&str, Ssrc> {
         "ssrc",
         map(
             tuple((
-                wsf(read_big_number), // id
-                preceded(
-                    multispace0,
-                    separated_pair(
-                        cowify(read_non_colon_string),
-                        tag(":"),
-                        cowify(wsf(is_not("\n"))),
-                    ),
-                ),
+                wsf(read_ssrc_id),                     // id
+                preceded(multispace0, ssrc_attribute), // attribute
             )),
-            |(id, (attribute, value))| Ssrc {
-                id,
-                attribute,
-                value,
-            },
+            |(id, attribute)| Ssrc { id, attribute },
         ),
     )(input)
 }
@@ -58,13 +128,13 @@ fn test_ssrc_line() {
     assert_line!(
         ssrc_line,
         "a=ssrc:1366781084 cname:EocUG1f0fcg/yvY7",
-        Ssrc { id: 1366781084, attribute: "cname".into(), value: "EocUG1f0fcg/yvY7".into() },
+        Ssrc { id: 1366781084, attribute: SsrcAttribute::Cname("EocUG1f0fcg/yvY7".into()) },
         print
     );
     assert_line!(
         ssrc_line,
         "a=ssrc: 1366781084 cname: EocUG1f0fcg/yvY7",
-        Ssrc { id: 1366781084, attribute: "cname".into(), value: "EocUG1f0fcg/yvY7".into() }
+        Ssrc { id: 1366781084, attribute: SsrcAttribute::Cname("EocUG1f0fcg/yvY7".into()) }
     );
     assert_line!(ssrc_line, "a=ssrc:3570614608 cname:4TOk42mSjXCkVIa6");
     assert_line!(ssrc_line, "a=ssrc:3570614608 msid:lgsCFqt9kN2fVKw5wg3NKqGdATQoltEwOdMS 35429d94-5637-4686-9ecd-7d0622261ce8");
@@ -76,9 +146,34 @@ fn test_ssrc_line() {
     assert_line!(ssrc_line, "a=ssrc:2231627014 label:daed9400-d0dd-4db3-b949-422499e96e2d");
     assert_line!(ssrc_line, "a=ssrc:632943048 cname:4TOk42mSjXCkVIa6");
     assert_line!(ssrc_line, "a=ssrc:632943048 msid:lgsCFqt9kN2fVKw5wg3NKqGdATQoltEwOdMS daed9400-d0dd-4db3-b949-422499e96e2d");
+    assert_line!(
+        ssrc_line,
+        "a=ssrc:632943048 msid:stream track",
+        Ssrc { id: 632943048, attribute: SsrcAttribute::Msid(Msid { stream_id: Some("stream".into()), track_id: Some("track".into()) }) },
+        print
+    );
+    assert_line!(
+        ssrc_line,
+        "a=ssrc:632943048 msid:stream",
+        Ssrc { id: 632943048, attribute: SsrcAttribute::Msid(Msid { stream_id: Some("stream".into()), track_id: None }) },
+        print
+    );
+    assert_line!(
+        ssrc_line,
+        "a=ssrc:632943048 previous-ssrc:1366781084 2231627014",
+        Ssrc { id: 632943048, attribute: SsrcAttribute::PreviousSsrc(vec![1366781084, 2231627014]) },
+        print
+    );
+    assert_line!(
+        ssrc_line,
+        "a=ssrc:632943048 fmtp:96 apt=100",
+        Ssrc { id: 632943048, attribute: SsrcAttribute::Other { attribute: "fmtp".into(), value: "96 apt=100".into() } },
+        print
+    );
 }
 
-#[derive(Clone, PartialEq, Eq)]
+/// Semantics of an `a=ssrc-group` line
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[cfg_attr(
     feature = "serde",
@@ -86,23 +181,64 @@ fn test_ssrc_line() {
     serde(rename_all = "camelCase")
 )]
 #[non_exhaustive]
-pub enum SsrcSemantic {
+pub enum SsrcSemantic<'a> {
+    /// `FID` flow identification, e.g. RTX <https://tools.ietf.org/html/rfc5576#section-4.2>
     FID,
+    /// `FEC` <https://tools.ietf.org/html/rfc5576#section-4.3>
     FEC,
+    /// `FEC-FR` <https://tools.ietf.org/html/rfc5956#section-4.3>
+    #[allow(non_camel_case_types)]
+    FEC_FR,
+    /// `SIM` simulcast layers as used by Chrome
+    SIM,
+    /// `DUP` duplication <https://tools.ietf.org/html/rfc7104>
+    DUP,
+    Other(Cow<'a, str>),
 }
 
-#[derive(Clone, PartialEq, Eq)]
+impl SsrcSemantic<'_> {
+    pub fn as_str(&self) -> &str {
+        match self {
+            SsrcSemantic::FID => "FID",
+            SsrcSemantic::FEC => "FEC",
+            SsrcSemantic::FEC_FR => "FEC-FR",
+            SsrcSemantic::SIM => "SIM",
+            SsrcSemantic::DUP => "DUP",
+            SsrcSemantic::Other(other) => other.as_ref(),
+        }
+    }
+}
+
+pub fn read_ssrc_semantic(input: &str) -> IResult<&str, SsrcSemantic> {
+    map(read_string, |semantic| match semantic {
+        "FID" => SsrcSemantic::FID,
+        "FEC" => SsrcSemantic::FEC,
+        "FEC-FR" => SsrcSemantic::FEC_FR,
+        "SIM" => SsrcSemantic::SIM,
+        "DUP" => SsrcSemantic::DUP,
+        other => SsrcSemantic::Other(other.into()),
+    })(input)
+}
+
+/// `a=ssrc-group:FID 2231627014 632943048`
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[cfg_attr(
     feature = "serde",
     derive(serde::Serialize, serde::Deserialize),
     serde(rename_all = "camelCase")
 )]
-pub struct SsrcGroup {
-    pub semantic: SsrcSemantic,
+pub struct SsrcGroup<'a> {
+    pub semantic: SsrcSemantic<'a>,
     pub ids: Vec<u32>,
 }
 
+impl SsrcGroup<'_> {
+    pub fn contains(&self, id: u32) -> bool {
+        self.ids.contains(&id)
+    }
+}
+
 pub fn ssrc_group_line(input: &str) -> IResult<&str, SsrcGroup> {
     attribute("ssrc-group", ssrc_group)(input)
 }
@@ -110,12 +246,8 @@ pub fn ssrc_group_line(input: &str) -> IResult<&str, SsrcGroup> {
 pub fn ssrc_group(input: &str) -> IResult<&str, SsrcGroup> {
     map(
         tuple((
-            alt((
-                // semantic
-                map(tag("FID"), |_| SsrcSemantic::FID),
-                map(tag("FEC"), |_| SsrcSemantic::FEC),
-            )),
-            wsf(read_as_numbers), // ids
+            read_ssrc_semantic, // semantic
+            wsf(read_ssrc_ids), // ids
         )),
         |(semantic, ids)| SsrcGroup { semantic, ids },
     )(input)
@@ -125,4 +257,160 @@ pub fn ssrc_group(input: &str) -> IResult<&str, SsrcGroup> {
 #[rustfmt::skip]
 fn test_ssrc_group_line() {
     assert_line_print!(ssrc_group_line, "a=ssrc-group:FID 2231627014 632943048");
+    assert_line!(
+        ssrc_group_line,
+        "a=ssrc-group:SIM 1 2 4294967295",
+        SsrcGroup { semantic: SsrcSemantic::SIM, ids: vec![1, 2, 4294967295] },
+        print
+    );
+    assert_line!(
+        ssrc_group_line,
+        "a=ssrc-group:FEC-FR 1366781084 1366781085",
+        SsrcGroup { semantic: SsrcSemantic::FEC_FR, ids: vec![1366781084, 1366781085] },
+        print
+    );
+    assert_line!(
+        ssrc_group_line,
+        "a=ssrc-group:FOO 1",
+        SsrcGroup { semantic: SsrcSemantic::Other("FOO".into()), ids: vec![1] },
+        print
+    );
+    assert_line_print!(ssrc_group_line, "a=ssrc-group:DUP 1366781084 1366781085");
+    assert!(ssrc_group_line("a=ssrc-group:SIM 4294967296").is_err());
+    assert!(ssrc_line("a=ssrc:4294967296 cname:foo").is_err());
+}
+
+/// Everything known about one SSRC of a media section
+///
+/// Repair and FEC SSRCs from `FID`, `FEC` and `FEC-FR` groups are attached to their primary SSRC
+/// instead of getting a stream of their own.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct SsrcStream<'s, 'a> {
+    pub ssrc: u32,
+    pub cname: Option<&'s str>,
+    pub stream_id: Option<&'s str>,
+    pub track_id: Option<&'s str>,
+    pub mslabel: Option<&'s str>,
+    pub label: Option<&'s str>,
+    pub previous_ssrc: &'s [u32],
+    /// the retransmission SSRC from `a=ssrc-group:FID`
+    pub rtx: Option<u32>,
+    /// the FEC SSRC from `a=ssrc-group:FEC` or `a=ssrc-group:FEC-FR`
+    pub fec: Option<u32>,
+    /// all `a=ssrc` lines of this SSRC, including the ones above
+    pub attributes: Vec<&'s SsrcAttribute<'a>>,
+}
+
+impl<'s, 'a> SsrcStream<'s, 'a> {
+    fn new(ssrc: u32) -> Self {
+        SsrcStream {
+            ssrc,
+            cname: None,
+            stream_id: None,
+            track_id: None,
+            mslabel: None,
+            label: None,
+            previous_ssrc: &[],
+            rtx: None,
+            fec: None,
+            attributes: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, attribute: &'s SsrcAttribute<'a>) {
+        match attribute {
+            SsrcAttribute::Cname(cname) => self.cname = Some(cname),
+            SsrcAttribute::Msid(msid) => {
+                self.stream_id = msid.stream_id.as_deref();
+                self.track_id = msid.track_id.as_deref();
+            }
+            SsrcAttribute::MsLabel(mslabel) => self.mslabel = Some(mslabel),
+            SsrcAttribute::Label(label) => self.label = Some(label),
+            SsrcAttribute::PreviousSsrc(ids) => self.previous_ssrc = ids,
+            SsrcAttribute::Other { .. } => {}
+        }
+        self.attributes.push(attribute);
+    }
+
+    /// The primary SSRC and its retransmission SSRC, if there is one.
+    pub fn rtx_pair(&self) -> (u32, Option<u32>) {
+        (self.ssrc, self.rtx)
+    }
+}
+
+/// Groups `a=ssrc` lines per primary SSRC, in the order they first appear.
+pub fn ssrc_streams<'s, 'a>(
+    ssrcs: &'s [Ssrc<'a>],
+    groups: &[SsrcGroup<'a>],
+) -> Vec<SsrcStream<'s, 'a>> {
+    let secondary = |semantic: &SsrcSemantic| {
+        matches!(
+            semantic,
+            SsrcSemantic::FID | SsrcSemantic::FEC | SsrcSemantic::FEC_FR
+        )
+    };
+    let primary_of = |id: u32| {
+        groups
+            .iter()
+            .filter(|group| secondary(&group.semantic))
+            .find(|group| group.ids.iter().skip(1).any(|&i| i == id))
+            .and_then(|group| group.ids.first().copied())
+    };
+
+    let mut streams: Vec<SsrcStream> = Vec::new();
+    for ssrc in ssrcs {
+        if primary_of(ssrc.id).is_some() {
+            continue;
+        }
+        if let Some(stream) = streams.iter_mut().find(|s| s.ssrc == ssrc.id) {
+            stream.add(&ssrc.attribute);
+        } else {
+            let mut stream = SsrcStream::new(ssrc.id);
+            stream.add(&ssrc.attribute);
+            streams.push(stream);
+        }
+    }
+
+    for group in groups.iter().filter(|group| secondary(&group.semantic)) {
+        if let (Some(primary), Some(&repair)) = (group.ids.first(), group.ids.get(1)) {
+            if let Some(stream) = streams.iter_mut().find(|s| s.ssrc == *primary) {
+                match group.semantic {
+                    SsrcSemantic::FID => stream.rtx = Some(repair),
+                    _ => stream.fec = Some(repair),
+                }
+            }
+        }
+    }
+    streams
+}
+
+#[test]
+fn test_ssrc_streams() {
+    let lines = [
+        "a=ssrc:1 cname:foo",
+        "a=ssrc:1 msid:stream track",
+        "a=ssrc:2 cname:foo",
+        "a=ssrc:2 msid:stream track",
+        "a=ssrc:3 cname:foo",
+        "a=ssrc:3 mslabel:stream",
+        "a=ssrc:3 label:other",
+    ];
+    let ssrcs = lines
+        .iter()
+        .map(|line| ssrc_line(line).unwrap().1)
+        .collect::<Vec<_>>();
+    let groups = [ssrc_group_line("a=ssrc-group:FID 1 2").unwrap().1];
+
+    let streams = ssrc_streams(&ssrcs, &groups);
+    assert_eq!(streams.len(), 2);
+    assert_eq!(streams[0].rtx_pair(), (1, Some(2)));
+    assert_eq!(streams[0].cname, Some("foo"));
+    assert_eq!(streams[0].stream_id, Some("stream"));
+    assert_eq!(streams[0].track_id, Some("track"));
+    assert_eq!(streams[0].attributes.len(), 2);
+    assert_eq!(streams[1].rtx_pair(), (3, None));
+    assert_eq!(streams[1].mslabel, Some("stream"));
+    assert_eq!(streams[1].label, Some("other"));
+    assert_eq!(streams[1].track_id, None);
 }
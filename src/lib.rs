@@ -36,6 +36,9 @@ pub mod lines;
 mod sdp_line;
 
 pub mod media_section;
+pub mod negotiation;
+pub mod policy;
+pub mod sdpfrag;
 pub mod session;
 
 mod parsers;
@@ -4,14 +4,15 @@ use derive_into_owned::IntoOwned;
 
 use crate::{
     attributes::{
-        candidate, dtls, extmap, ice::IceParameter, msid, rtcp, rtpmap, AttributeLine, BundleGroup,
-        Control, Direction, Fingerprint, Fmtp, Ice, RtcpOption, Rtp, Ssrc, SsrcGroup,
+        candidate, dtls, extmap, ice, ice::IceParameter, msid, rtcp, rtpmap, ssrc_streams,
+        AttributeLine, Control, Direction, Fingerprint, Fmtp, Group, Ice, RtcpOption, Rtp, Ssrc,
+        SsrcGroup, SsrcStream,
     },
     lines::{connection::Connection, media::Media, SessionLine},
-    SdpLine,
+    SdpLine, Session,
 };
 
-#[derive(Default, IntoOwned, PartialEq, Eq)]
+#[derive(Clone, Default, IntoOwned, PartialEq, Eq)]
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[cfg_attr(
     feature = "serde",
@@ -30,23 +31,33 @@ pub struct MediaSection<'a> {
     pub ice: Ice<'a>,
     pub mid: Option<Cow<'a, str>>,
     pub msid_semantic: Option<msid::MsidSemantic<'a>>,
-    pub msid: Option<msid::Msid<'a>>,
+    pub msids: Vec<msid::Msid<'a>>,
     pub rtp_map: Vec<rtpmap::RtpMap<'a>>,
     pub p_time: Option<rtpmap::PTime>,
     pub ssrc: Vec<Ssrc<'a>>,
-    pub bundle_group: Option<BundleGroup<'a>>,
+    pub groups: Vec<Group<'a>>,
     pub bundle_only: bool,
-    pub ssrc_group: Option<SsrcGroup>,
-    pub fingerprint: Option<Fingerprint<'a>>,
+    pub ssrc_groups: Vec<SsrcGroup<'a>>,
+    pub fingerprints: Vec<Fingerprint<'a>>,
     pub direction: Option<Direction>,
     pub rtp: Option<Rtp<'a>>,
-    pub rtcp: Option<rtcp::Rtcp>,
+    pub rtcp: Option<rtcp::Rtcp<'a>>,
     pub fmtp: Vec<Fmtp<'a>>,
     pub rtcp_fb: Vec<rtcp::Fb<'a>>,
+    /// `a=rtcp-xr`
+    pub rtcp_xr: Option<rtcp::RtcpXr<'a>>,
     pub rtcp_option: Vec<RtcpOption>,
     pub control: Option<Control<'a>>,
     pub setup_role: Option<dtls::SetupRole>,
+    /// `a=connection:new|existing`
+    pub dtls_connection: Option<dtls::ConnectionMode>,
+    /// `a=tls-id`
+    pub tls_id: Option<dtls::TlsId<'a>>,
     pub extmap: Vec<extmap::Extmap<'a>>,
+    /// `a=extmap-allow-mixed`
+    pub extmap_allow_mixed: bool,
+    /// `a=cryptex`
+    pub cryptex: bool,
 
     pub attributes: Vec<AttributeLine<'a>>,
 }
@@ -60,6 +71,157 @@ impl<'a> MediaSection<'a> {
             payloads: self.payloads.clone(),
         }
     }
+
+    /// The direction this media section is actually in.
+    ///
+    /// Falls back to a session-level direction attribute and `sendrecv` if there is none,
+    /// a rejected media section (port `0`) is always `inactive`.
+    pub fn effective_direction(&self, session: &Session) -> Direction {
+        if self.port == 0 {
+            return Direction::Inactive;
+        }
+        self.direction
+            .or_else(|| {
+                session
+                    .attributes
+                    .iter()
+                    .find_map(|attr| attr.as_direction().copied())
+            })
+            .unwrap_or(Direction::SendRecv)
+    }
+
+    /// Sorts the candidates by priority, most preferred first.
+    pub fn sort_candidates(&mut self) {
+        self.candidates
+            .sort_by_key(|candidate| std::cmp::Reverse(candidate.priority));
+    }
+
+    /// The id negotiated for `extension` by `a=extmap`.
+    pub fn extmap_id(&self, extension: &extmap::RtpHeaderExtension) -> Option<u32> {
+        self.extmap
+            .iter()
+            .find(|extmap| extmap.extension() == *extension)
+            .map(|extmap| extmap.value)
+    }
+
+    /// Adds an `a=extmap` for `extension` unless there already is one and returns its id.
+    ///
+    /// The lowest free one-byte header id is picked.
+    /// Once those are exhausted two-byte header ids are used if `two_byte` is set,
+    /// which is only allowed if both sides support two-byte headers,
+    /// e.g. because of [`MediaSection::allows_mixed_extmap`].
+    /// Returns `None` if every usable id is taken.
+    pub fn add_extension(
+        &mut self,
+        extension: extmap::RtpHeaderExtension<'a>,
+        direction: Option<Direction>,
+        two_byte: bool,
+    ) -> Option<u32> {
+        if let Some(id) = self.extmap_id(&extension) {
+            return Some(id);
+        }
+        let id = extmap::ONE_BYTE_IDS
+            .chain(extmap::TWO_BYTE_IDS.filter(|_| two_byte))
+            .find(|id| self.extmap.iter().all(|extmap| extmap.value != *id))?;
+        self.extmap.push(extmap::Extmap {
+            value: id,
+            direction,
+            uri: extension.into_uri(),
+            encrypted: false,
+            attributes: Vec::new(),
+        });
+        Some(id)
+    }
+
+    /// Whether one-byte and two-byte header extensions may be mixed, per `a=extmap-allow-mixed` here or at session level.
+    pub fn allows_mixed_extmap(&self, session: &Session) -> bool {
+        self.extmap_allow_mixed || session.extmap_allow_mixed
+    }
+
+    /// Whether all RTP header extensions are encrypted, per `a=cryptex` here or at session level.
+    pub fn uses_cryptex(&self, session: &Session) -> bool {
+        self.cryptex || session.cryptex
+    }
+
+    /// Whether `extension` is transmitted encrypted,
+    /// either because of `a=cryptex` or because its `a=extmap` uses `urn:ietf:params:rtp-hdrext:encrypt`.
+    ///
+    /// Returns `None` if `extension` wasn't negotiated.
+    pub fn is_extension_encrypted(
+        &self,
+        session: &Session,
+        extension: &extmap::RtpHeaderExtension,
+    ) -> Option<bool> {
+        let extmap = self
+            .extmap
+            .iter()
+            .find(|extmap| extmap.extension() == *extension)?;
+        Some(extmap.encrypted || self.uses_cryptex(session))
+    }
+
+    /// Where RTCP for this media section is sent to, `None` if it is rejected or has no address.
+    ///
+    /// With `a=rtcp-mux` that's the RTP transport, otherwise `a=rtcp` or the next port after the RTP port,
+    /// if there is one below 65536.
+    /// Addresses not given by `a=rtcp` are taken from `c=` here or at session level.
+    pub fn rtcp_transport_address(
+        &self,
+        session: &Session,
+    ) -> Option<(candidate::CandidateAddress<'a>, u32)> {
+        if self.port == 0 {
+            return None;
+        }
+        let connection = self.connection.as_ref().or(session.connection.as_ref());
+        let mux = self
+            .rtcp_option
+            .iter()
+            .any(|o| matches!(o, RtcpOption::RtcpMux | RtcpOption::RtcpMuxOnly));
+        match &self.rtcp {
+            _ if mux => Some((connection?.addr.into(), self.port)),
+            Some(rtcp) => rtcp.transport_address(connection),
+            None if self.port >= u32::from(u16::MAX) => None,
+            None => Some((connection?.addr.into(), self.port + 1)),
+        }
+    }
+
+    /// The candidates from `a=remote-candidates`, the controlling agent's choice after ICE completed.
+    pub fn remote_candidates(&self) -> &[ice::RemoteCandidate<'a>] {
+        self.attributes
+            .iter()
+            .find_map(|attr| match attr {
+                AttributeLine::Ice(IceParameter::RemoteCandidates(candidates)) => {
+                    Some(candidates.as_slice())
+                }
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// The ids of all streams this media section's track belongs to, per `a=msid`.
+    pub fn stream_ids(&self) -> impl Iterator<Item = &str> {
+        self.msids
+            .iter()
+            .filter_map(|msid| msid.stream_id.as_deref())
+    }
+
+    /// The track id from `a=msid`, all `a=msid` lines of a media section name the same track.
+    pub fn track_id(&self) -> Option<&str> {
+        self.msids.iter().find_map(|msid| msid.track_id.as_deref())
+    }
+
+    /// The `a=ssrc` lines grouped per primary SSRC, with RTX and FEC SSRCs from `a=ssrc-group` attached.
+    pub fn ssrc_streams(&self) -> Vec<SsrcStream<'_, 'a>> {
+        ssrc_streams(&self.ssrc, &self.ssrc_groups)
+    }
+
+    /// The SSRC streams that carry the track with this id, per `a=ssrc:<id> msid:<stream> <track>`.
+    pub fn ssrc_streams_for_track(&self, track_id: &str) -> Vec<SsrcStream<'_, 'a>> {
+        self.ssrc_streams()
+            .into_iter()
+            .filter(|stream| stream.track_id == Some(track_id))
+            .collect()
+    }
+
     pub(crate) fn add_line(&mut self, line: SdpLine<'a>) {
         use AttributeLine::*;
         use SessionLine::*;
@@ -71,7 +233,7 @@ impl<'a> MediaSection<'a> {
             #[cfg(all(feature = "udisplay", not(feature = "debug")))]
             SdpLine::Session(session) => eprintln!("🔥 {}", crate::ufmt_to_string(&session)),
             #[cfg(not(all(feature = "udisplay", feature = "debug")))]
-            SdpLine::Session(_session) => {},
+            SdpLine::Session(_session) => {}
 
             SdpLine::Attribute(Candidate(candidate)) => self.candidates.push(candidate),
             SdpLine::Attribute(Ice(IceParameter::Options(o))) => self.ice.options = Some(o),
@@ -80,23 +242,28 @@ impl<'a> MediaSection<'a> {
             SdpLine::Attribute(attr @ Ice(_)) => self.attributes.push(attr),
             SdpLine::Attribute(Mid(mid)) => self.mid = Some(mid.0),
             SdpLine::Attribute(MsidSemantic(semantic)) => self.msid_semantic = Some(semantic),
-            SdpLine::Attribute(Msid(msid)) => self.msid = Some(msid),
+            SdpLine::Attribute(Msid(msid)) => self.msids.push(msid),
             SdpLine::Attribute(RtpMap(rtp_map)) => self.rtp_map.push(rtp_map),
             SdpLine::Attribute(PTime(p_time)) => self.p_time = Some(p_time),
             SdpLine::Attribute(Ssrc(ssrc)) => self.ssrc.push(ssrc),
-            SdpLine::Attribute(BundleGroup(bundle_group)) => self.bundle_group = Some(bundle_group),
-            SdpLine::Attribute(SsrcGroup(ssrc_group)) => self.ssrc_group = Some(ssrc_group),
-            SdpLine::Attribute(Fingerprint(fingerprint)) => self.fingerprint = Some(fingerprint),
+            SdpLine::Attribute(Group(group)) => self.groups.push(group),
+            SdpLine::Attribute(SsrcGroup(ssrc_group)) => self.ssrc_groups.push(ssrc_group),
+            SdpLine::Attribute(Fingerprint(fingerprint)) => self.fingerprints.push(fingerprint),
             SdpLine::Attribute(Direction(direction)) => self.direction = Some(direction),
 
             SdpLine::Attribute(Rtp(rtp)) => self.rtp = Some(rtp),
             SdpLine::Attribute(Rtcp(rtcp)) => self.rtcp = Some(rtcp),
             SdpLine::Attribute(Fmtp(fmtp)) => self.fmtp.push(fmtp),
             SdpLine::Attribute(RtcpFb(rtcp_fb)) => self.rtcp_fb.push(rtcp_fb),
+            SdpLine::Attribute(RtcpXr(rtcp_xr)) => self.rtcp_xr = Some(rtcp_xr),
             SdpLine::Attribute(RtcpOption(rtcp_option)) => self.rtcp_option.push(rtcp_option),
             SdpLine::Attribute(Control(control)) => self.control = Some(control),
             SdpLine::Attribute(SetupRole(setup_role)) => self.setup_role = Some(setup_role),
+            SdpLine::Attribute(DtlsConnection(mode)) => self.dtls_connection = Some(mode),
+            SdpLine::Attribute(TlsId(tls_id)) => self.tls_id = Some(tls_id),
             SdpLine::Attribute(Extmap(extmap)) => self.extmap.push(extmap),
+            SdpLine::Attribute(ExtmapAllowMixed) => self.extmap_allow_mixed = true,
+            SdpLine::Attribute(Cryptex) => self.cryptex = true,
             SdpLine::Attribute(AttributeLine::BundleOnly) => self.bundle_only = true,
             SdpLine::Attribute(attr) => self.attributes.push(attr),
             SdpLine::Comment(_) => {}
@@ -115,3 +282,248 @@ impl<'a> From<Media<'a>> for MediaSection<'a> {
         }
     }
 }
+
+#[test]
+fn test_effective_direction() {
+    let session = Session::read_str(
+        "v=0
+a=inactive
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=sendonly
+m=audio 0 UDP/TLS/RTP/SAVPF 111
+a=sendrecv",
+    );
+    let directions = session
+        .media
+        .iter()
+        .map(|m| m.effective_direction(&session))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        directions,
+        [
+            Direction::Inactive,
+            Direction::SendOnly,
+            Direction::Inactive
+        ]
+    );
+    assert_eq!(
+        Session::read_str("v=0\nm=audio 9 RTP/AVP 0").media[0]
+            .effective_direction(&Session::default()),
+        Direction::SendRecv
+    );
+}
+
+#[test]
+fn test_simulcast_ssrc_groups() {
+    let session = Session::read_str(
+        "v=0
+m=video 9 UDP/TLS/RTP/SAVPF 96 97
+a=ssrc-group:SIM 1 3 5
+a=ssrc-group:FID 1 2
+a=ssrc-group:FID 3 4
+a=ssrc-group:FID 5 6
+a=ssrc:1 cname:foo",
+    );
+    let groups = &session.media[0].ssrc_groups;
+    assert_eq!(groups.len(), 4);
+    assert_eq!(groups[0].semantic, crate::attributes::SsrcSemantic::SIM);
+    assert!(groups[3].contains(6));
+    assert_eq!(session.media[0].ssrc[0].id, 1);
+
+    let streams = session.media[0].ssrc_streams();
+    assert_eq!(streams.len(), 1);
+    assert_eq!(streams[0].rtx_pair(), (1, Some(2)));
+}
+
+#[test]
+fn test_sort_candidates() {
+    let mut session = Session::read_str(
+        "v=0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=candidate:1 1 udp 25108223 237.30.30.30 58779 typ relay
+a=candidate:2 1 udp 2122260223 192.168.0.196 46243 typ host
+a=candidate:3 1 udp 1518280447 47.61.61.61 36768 typ srflx
+a=candidate:4 1 udp 2122260223 192.168.0.197 46243 typ host",
+    );
+    session.media[0].sort_candidates();
+    let foundations = session.media[0]
+        .candidates
+        .iter()
+        .map(|c| c.foundation.as_ref())
+        .collect::<Vec<_>>();
+    assert_eq!(foundations, ["2", "4", "3", "1"]);
+}
+
+#[test]
+fn test_msids() {
+    let session = Session::read_str(
+        "v=0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=msid:stream-a track
+a=msid:stream-b track
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=msid:- track",
+    );
+    assert_eq!(
+        session.media[0].stream_ids().collect::<Vec<_>>(),
+        ["stream-a", "stream-b"]
+    );
+    assert_eq!(session.media[0].track_id(), Some("track"));
+    assert_eq!(session.media[1].stream_ids().count(), 0);
+    assert_eq!(session.media[1].track_id(), Some("track"));
+}
+
+#[test]
+fn test_remote_candidates() {
+    let session = Session::read_str(
+        "v=0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=remote-candidates:1 192.0.2.3 45664 2 192.0.2.3 45665
+m=audio 9 UDP/TLS/RTP/SAVPF 111",
+    );
+    let remote = session.media[0].remote_candidates();
+    assert_eq!(remote.len(), 2);
+    assert_eq!(remote[1].component, 2);
+    assert_eq!(remote[1].port, 45665);
+    assert!(session.media[1].remote_candidates().is_empty());
+}
+
+#[test]
+fn test_add_extension() {
+    use extmap::RtpHeaderExtension;
+
+    let mut session = Session::read_str(
+        "v=0
+m=video 9 UDP/TLS/RTP/SAVPF 96
+a=extmap:1 urn:ietf:params:rtp-hdrext:toffset
+a=extmap:2 http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time
+a=extmap:4 urn:ietf:params:rtp-hdrext:sdes:mid
+a=extmap-allow-mixed",
+    );
+    assert!(!session.extmap_allow_mixed);
+    assert!(session.media[0].allows_mixed_extmap(&session));
+    let two_byte = session.media[0].allows_mixed_extmap(&session);
+
+    let media = &mut session.media[0];
+    assert_eq!(media.extmap_id(&RtpHeaderExtension::Mid), Some(4));
+    assert_eq!(media.extmap_id(&RtpHeaderExtension::TransportCc), None);
+    assert_eq!(
+        media.add_extension(RtpHeaderExtension::TransportCc, None, false),
+        Some(3)
+    );
+    assert_eq!(
+        media.add_extension(RtpHeaderExtension::AbsSendTime, None, false),
+        Some(2)
+    );
+    for _ in 5..=14 {
+        media.add_extension(
+            RtpHeaderExtension::Other(format!("urn:example:{}", media.extmap.len()).into()),
+            None,
+            false,
+        );
+    }
+    // one-byte ids are exhausted
+    assert_eq!(
+        media.add_extension(
+            RtpHeaderExtension::DependencyDescriptor,
+            Some(Direction::SendOnly),
+            false
+        ),
+        None
+    );
+    assert_eq!(
+        media.add_extension(
+            RtpHeaderExtension::DependencyDescriptor,
+            Some(Direction::SendOnly),
+            two_byte
+        ),
+        Some(15)
+    );
+    assert_eq!(
+        media.extmap.last().unwrap().uri,
+        "https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension"
+    );
+}
+
+#[test]
+fn test_encrypted_extensions() {
+    use extmap::RtpHeaderExtension;
+
+    let fixture = std::fs::read_to_string(
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/sdp_transform/extmap-encrypt.sdp"),
+    )
+    .unwrap();
+    let session = Session::read_str(&fixture);
+    let media = &session.media[0];
+    let smpte_tc = RtpHeaderExtension::Other("urn:ietf:params:rtp-hdrext:smpte-tc".into());
+    let toffset = RtpHeaderExtension::Other("urn:ietf:params:rtp-hdrext:toffset".into());
+    assert!(!media.uses_cryptex(&session));
+    assert_eq!(media.extmap_id(&smpte_tc), Some(3));
+    assert_eq!(
+        media.is_extension_encrypted(&session, &smpte_tc),
+        Some(true)
+    );
+    assert_eq!(
+        media.is_extension_encrypted(&session, &toffset),
+        Some(false)
+    );
+    assert_eq!(
+        media.is_extension_encrypted(&session, &RtpHeaderExtension::Mid),
+        None
+    );
+
+    let session = Session::read_str(
+        "v=0
+a=cryptex
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=extmap:2 urn:ietf:params:rtp-hdrext:toffset
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=cryptex",
+    );
+    assert!(session.cryptex);
+    assert!(!session.media[0].cryptex);
+    assert!(session.media[1].cryptex);
+    assert_eq!(
+        session.media[0].is_extension_encrypted(&session, &toffset),
+        Some(true)
+    );
+}
+
+#[test]
+fn test_rtcp_transport_address() {
+    use candidate::CandidateAddress;
+
+    let session = Session::read_str(
+        "v=0
+c=IN IP4 203.0.113.1
+m=audio 49170 RTP/AVP 0
+m=audio 49172 RTP/AVP 0
+a=rtcp:53020
+m=audio 49174 RTP/AVP 0
+a=rtcp:53020 IN IP4 rtcp.example.com
+m=audio 49176 RTP/AVP 0
+a=rtcp:53020
+a=rtcp-mux
+m=audio 0 RTP/AVP 0
+m=audio 65535 RTP/AVP 0",
+    );
+    let addr = CandidateAddress::Ip("203.0.113.1".parse().unwrap());
+    let addresses = session
+        .media
+        .iter()
+        .map(|m| m.rtcp_transport_address(&session))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        addresses,
+        [
+            Some((addr.clone(), 49171)),
+            Some((addr.clone(), 53020)),
+            Some((CandidateAddress::Fqdn("rtcp.example.com".into()), 53020)),
+            Some((addr, 49176)),
+            None,
+            None,
+        ]
+    );
+}
@@ -0,0 +1,532 @@
+//! [SDP Offer/Answer](https://tools.ietf.org/html/rfc3264) negotiation
+//!
+//! Takes a remote offer and a description of what we can do locally and produces an answer.
+
+use std::borrow::Cow;
+
+use derive_into_owned::IntoOwned;
+
+use crate::{
+    attributes::{
+        dtls::SetupRole, extmap::Extmap, rtcp::intersect_feedback, rtcp::FbVal, rtcp::RtcpXr,
+        rtpmap::RtpMap, Candidate, Direction, Fingerprint, Fmtp, Group, Ice, IceOptions,
+    },
+    lines::{
+        connection::Connection, origin::Origin, session_name::SessionName, timing::Timing,
+        version::Version,
+    },
+    media_section::MediaSection,
+    Session,
+};
+
+pub mod state;
+
+pub use state::*;
+
+/// A codec we are able to handle, matched against offered `a=rtpmap` lines by name, clock rate
+/// and channels.
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct CodecCapability<'a> {
+    pub name: Cow<'a, str>,
+    pub clock_rate: Option<u32>,
+    pub channels: Option<u32>,
+    /// `a=fmtp` config to answer with, mirrors the offered one if `None`
+    pub fmtp: Option<Cow<'a, str>>,
+    pub rtcp_fb: Vec<FbVal<'a>>,
+}
+
+/// What we can do for one kind of media (`audio`, `video`, `application`, ...).
+///
+/// If `codecs` is empty the offered formats are accepted as they are,
+/// which is what you want for non-RTP media like data channels.
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct MediaCapabilities<'a> {
+    pub r#type: Cow<'a, str>,
+    pub codecs: Vec<CodecCapability<'a>>,
+    pub direction: Direction,
+    /// supported header extension URIs
+    pub extensions: Vec<Cow<'a, str>>,
+    /// supported `a=rtcp-xr` report blocks
+    pub rtcp_xr: Option<RtcpXr<'a>>,
+}
+
+/// Transport parameters put into every accepted media section of the answer.
+#[derive(Default, Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct TransportCapabilities<'a> {
+    pub ice_ufrag: Option<Cow<'a, str>>,
+    pub ice_pwd: Option<Cow<'a, str>>,
+    pub ice_options: Option<IceOptions<'a>>,
+    pub fingerprints: Vec<Fingerprint<'a>>,
+    /// role to take if the offerer sent `a=setup:actpass`, defaults to `active`
+    pub setup_role: Option<SetupRole>,
+    pub connection: Option<Connection>,
+    pub candidates: Vec<Candidate<'a>>,
+}
+
+#[derive(Default, Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct LocalCapabilities<'a> {
+    /// `o=` line of the answer, the offer's origin is reused if `None`
+    pub origin: Option<Origin<'a>>,
+    pub media: Vec<MediaCapabilities<'a>>,
+    pub transport: TransportCapabilities<'a>,
+}
+
+impl<'a> LocalCapabilities<'a> {
+    fn media_for(&self, r#type: &str) -> Option<&MediaCapabilities<'a>> {
+        self.media.iter().find(|m| m.r#type == r#type)
+    }
+}
+
+/// Creates an answer to `offer`.
+///
+/// Media sections are answered in order, those we can't handle are rejected with port `0`.
+pub fn create_answer<'a>(offer: &Session<'a>, local: &LocalCapabilities<'a>) -> Session<'a> {
+    let media: Vec<MediaSection<'a>> = offer
+        .media
+        .iter()
+        .map(|offered| answer_media(offer, offered, local))
+        .collect();
+
+    let accepted_mids = media
+        .iter()
+        .filter(|m| m.port != 0)
+        .filter_map(|m| m.mid.clone())
+        .collect::<Vec<_>>();
+
+    let groups = offer
+        .groups
+        .iter()
+        .filter_map(|group| answer_group(group, &accepted_mids))
+        .collect();
+
+    Session {
+        version: Some(Version(0)),
+        origin: local.origin.clone().or_else(|| offer.origin.clone()),
+        name: Some(SessionName("-".into())),
+        timing: Some(Timing { start: 0, stop: 0 }),
+        groups,
+        media,
+        ..Default::default()
+    }
+}
+
+/// mirrors a group of the offer without the rejected media sections
+fn answer_group<'a>(group: &Group<'a>, accepted_mids: &[Cow<'a, str>]) -> Option<Group<'a>> {
+    let mids = group
+        .mids
+        .iter()
+        .filter(|mid| accepted_mids.contains(mid))
+        .cloned()
+        .collect::<Vec<_>>();
+    if mids.is_empty() {
+        None
+    } else {
+        Some(Group {
+            semantics: group.semantics.clone(),
+            mids,
+        })
+    }
+}
+
+fn reject<'a>(offered: &MediaSection<'a>) -> MediaSection<'a> {
+    MediaSection {
+        r#type: offered.r#type.clone(),
+        port: 0,
+        protocol: offered.protocol.clone(),
+        payloads: offered.payloads.clone(),
+        mid: offered.mid.clone(),
+        ..Default::default()
+    }
+}
+
+fn answer_media<'a>(
+    offer: &Session<'a>,
+    offered: &MediaSection<'a>,
+    local: &LocalCapabilities<'a>,
+) -> MediaSection<'a> {
+    let capabilities = match local.media_for(&offered.r#type) {
+        Some(capabilities) if offered.port != 0 => capabilities,
+        _ => return reject(offered),
+    };
+
+    let mut answer = MediaSection {
+        r#type: offered.r#type.clone(),
+        port: 9,
+        protocol: offered.protocol.clone(),
+        mid: offered.mid.clone(),
+        ..Default::default()
+    };
+
+    if capabilities.codecs.is_empty() {
+        answer.payloads = offered.payloads.clone();
+    } else {
+        negotiate_codecs(offered, capabilities, &mut answer);
+        if answer.payloads.is_empty() {
+            return reject(offered);
+        }
+    }
+
+    answer.extmap = offered
+        .extmap
+        .iter()
+        .filter(|ext| capabilities.extensions.contains(&ext.uri))
+        .map(|ext| Extmap {
+            direction: ext.direction.map(Direction::reverse),
+            ..ext.clone()
+        })
+        .collect();
+
+    let offered_direction = offered.effective_direction(offer);
+    answer.direction = Some(
+        capabilities
+            .direction
+            .intersect(offered_direction.reverse()),
+    );
+    answer.rtcp_option = offered.rtcp_option.clone();
+    answer.rtcp_xr = offered
+        .rtcp_xr
+        .as_ref()
+        .zip(capabilities.rtcp_xr.as_ref())
+        .and_then(|(offered, supported)| offered.intersect(supported));
+    let own_mid = answer.mid.iter().cloned().collect::<Vec<_>>();
+    answer.groups = offered
+        .groups
+        .iter()
+        .filter_map(|group| answer_group(group, &own_mid))
+        .collect();
+
+    let transport = &local.transport;
+    answer.connection = transport.connection.clone();
+    answer.candidates = transport.candidates.clone();
+    answer.ice = Ice {
+        ufrag: transport.ice_ufrag.clone(),
+        pwd: transport.ice_pwd.clone(),
+        options: transport.ice_options.clone(),
+    };
+    answer.fingerprints = transport.fingerprints.clone();
+    answer.setup_role = offered
+        .setup_role
+        .as_ref()
+        .map(|offered_role| answer_setup_role(offered_role, transport.setup_role.as_ref()));
+
+    answer
+}
+
+fn negotiate_codecs<'a>(
+    offered: &MediaSection<'a>,
+    capabilities: &MediaCapabilities<'a>,
+    answer: &mut MediaSection<'a>,
+) {
+    let offered_payloads = offered
+        .payloads
+        .iter()
+        .filter_map(|p| p.parse::<u32>().ok())
+        .collect::<Vec<_>>();
+
+    let mut accepted: Vec<(&RtpMap<'a>, &CodecCapability<'a>)> = Vec::new();
+
+    // primary codecs first, retransmission can only be accepted for those
+    for payload in &offered_payloads {
+        let rtp_map = match offered.rtp_map.iter().find(|r| r.payload == *payload) {
+            Some(rtp_map) if !is_rtx(rtp_map) => rtp_map,
+            _ => continue,
+        };
+        if let Some(codec) = capabilities
+            .codecs
+            .iter()
+            .find(|c| codec_matches(rtp_map, c))
+        {
+            accepted.push((rtp_map, codec));
+        }
+    }
+    for payload in &offered_payloads {
+        let rtp_map = match offered.rtp_map.iter().find(|r| r.payload == *payload) {
+            Some(rtp_map) if is_rtx(rtp_map) => rtp_map,
+            _ => continue,
+        };
+        let associated =
+            offered_fmtp(offered, *payload).and_then(|config| associated_payload(config));
+        let associated_accepted = accepted.iter().any(|(r, _)| Some(r.payload) == associated);
+        if let Some(codec) = capabilities
+            .codecs
+            .iter()
+            .find(|c| codec_matches(rtp_map, c))
+        {
+            if associated_accepted {
+                accepted.push((rtp_map, codec));
+            }
+        }
+    }
+
+    for payload in &offered_payloads {
+        let (rtp_map, codec) = match accepted.iter().find(|(r, _)| r.payload == *payload) {
+            Some(found) => found,
+            None => continue,
+        };
+        answer.payloads.push(payload.to_string().into());
+        answer.rtp_map.push((*rtp_map).clone());
+
+        // the associated payload type of retransmission always refers to the offer
+        let config = if is_rtx(rtp_map) {
+            offered_fmtp(offered, *payload).cloned()
+        } else {
+            codec
+                .fmtp
+                .clone()
+                .or_else(|| offered_fmtp(offered, *payload).cloned())
+        };
+        if let Some(config) = config {
+            answer.fmtp.push(Fmtp {
+                payload: *payload,
+                config,
+            });
+        }
+
+        answer.rtcp_fb.extend(intersect_feedback(
+            &offered.rtcp_fb,
+            *payload,
+            &codec.rtcp_fb,
+        ));
+    }
+}
+
+fn codec_matches(offered: &RtpMap, codec: &CodecCapability) -> bool {
+    offered.encoding_name.eq_ignore_ascii_case(&codec.name)
+        && offered.clock_rate == codec.clock_rate
+        && offered.encoding.unwrap_or(1) == codec.channels.unwrap_or(1)
+}
+
+fn is_rtx(rtp_map: &RtpMap) -> bool {
+    rtp_map.encoding_name.eq_ignore_ascii_case("rtx")
+}
+
+fn offered_fmtp<'a, 'b>(offered: &'b MediaSection<'a>, payload: u32) -> Option<&'b Cow<'a, str>> {
+    offered
+        .fmtp
+        .iter()
+        .find(|f| f.payload == payload)
+        .map(|f| &f.config)
+}
+
+/// reads `apt=96` from an fmtp config
+fn associated_payload(config: &str) -> Option<u32> {
+    config
+        .split(';')
+        .filter_map(|param| param.trim().strip_prefix("apt="))
+        .find_map(|apt| apt.parse().ok())
+}
+
+fn answer_setup_role(offered: &SetupRole, preferred: Option<&SetupRole>) -> SetupRole {
+    match offered {
+        SetupRole::Active => SetupRole::Passive,
+        SetupRole::Passive => SetupRole::Active,
+        SetupRole::HoldConn => SetupRole::HoldConn,
+        SetupRole::ActPass => match preferred {
+            Some(SetupRole::Passive) => SetupRole::Passive,
+            _ => SetupRole::Active,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attributes::rtcp::{Fb, XrFormat};
+    use crate::attributes::GroupSemantics;
+
+    const OFFER: &str = "v=0
+o=- 4611731400430051336 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+a=group:BUNDLE 0 1 2
+m=audio 9 UDP/TLS/RTP/SAVPF 111 0
+c=IN IP4 0.0.0.0
+a=ice-ufrag:Oyef7uvBlwafI3hT
+a=ice-pwd:T0teqPLNQQOf+5W+ls+P2p16
+a=fingerprint:sha-256 49:66:12:17:0D:1C:91:AE:57:4C:C6:36:DD:D5:97:D2:7D:62:C9:9A:7F:B9:A3:F4:70:03:E7:43:91:73:23:5E
+a=setup:actpass
+a=mid:0
+a=extmap:1 urn:ietf:params:rtp-hdrext:ssrc-audio-level
+a=extmap:3 http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time
+a=sendonly
+a=rtcp-mux
+a=rtcp-xr:stat-summary=loss,jitt voip-metrics
+a=rtpmap:111 opus/48000/2
+a=fmtp:111 minptime=10;useinbandfec=1
+a=rtpmap:0 PCMU/8000
+m=video 9 UDP/TLS/RTP/SAVPF 96 97 98
+c=IN IP4 0.0.0.0
+a=setup:actpass
+a=mid:1
+a=sendrecv
+a=rtcp-mux
+a=rtpmap:96 VP8/90000
+a=rtcp-fb:96 goog-remb
+a=rtcp-fb:96 nack
+a=rtcp-fb:96 nack pli
+a=rtpmap:97 rtx/90000
+a=fmtp:97 apt=96
+a=rtpmap:98 rtx/90000
+a=fmtp:98 apt=100
+m=application 9 UDP/DTLS/SCTP webrtc-datachannel
+c=IN IP4 0.0.0.0
+a=mid:2
+";
+
+    fn local() -> LocalCapabilities<'static> {
+        LocalCapabilities {
+            origin: None,
+            media: vec![
+                MediaCapabilities {
+                    r#type: "audio".into(),
+                    codecs: vec![CodecCapability {
+                        name: "OPUS".into(),
+                        clock_rate: Some(48000),
+                        channels: Some(2),
+                        fmtp: None,
+                        rtcp_fb: vec![],
+                    }],
+                    direction: Direction::SendRecv,
+                    extensions: vec!["urn:ietf:params:rtp-hdrext:ssrc-audio-level".into()],
+                    rtcp_xr: Some(RtcpXr {
+                        formats: vec![XrFormat::VoipMetrics],
+                    }),
+                },
+                MediaCapabilities {
+                    r#type: "video".into(),
+                    codecs: vec![
+                        CodecCapability {
+                            name: "VP8".into(),
+                            clock_rate: Some(90000),
+                            channels: None,
+                            fmtp: None,
+                            rtcp_fb: vec![FbVal::GoogRemb],
+                        },
+                        CodecCapability {
+                            name: "rtx".into(),
+                            clock_rate: Some(90000),
+                            channels: None,
+                            fmtp: None,
+                            rtcp_fb: vec![],
+                        },
+                    ],
+                    direction: Direction::RecvOnly,
+                    extensions: vec![],
+                    rtcp_xr: None,
+                },
+            ],
+            transport: TransportCapabilities {
+                ice_ufrag: Some("local".into()),
+                ice_pwd: Some("localpasswordlocalpassword".into()),
+                setup_role: Some(SetupRole::Passive),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn answers_media_in_order() {
+        let offer = Session::read_str(OFFER);
+        let answer = create_answer(&offer, &local());
+
+        assert_eq!(answer.media.len(), 3);
+        let mids = answer
+            .media
+            .iter()
+            .map(|m| m.mid.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(mids, [Some("0"), Some("1"), Some("2")]);
+    }
+
+    #[test]
+    fn intersects_codecs() {
+        let offer = Session::read_str(OFFER);
+        let answer = create_answer(&offer, &local());
+
+        let audio = &answer.media[0];
+        assert_eq!(audio.payloads, ["111"]);
+        assert_eq!(audio.rtp_map.len(), 1);
+        assert_eq!(audio.fmtp[0].config, "minptime=10;useinbandfec=1");
+        assert_eq!(audio.extmap.len(), 1);
+        assert_eq!(audio.extmap[0].value, 1);
+        assert_eq!(
+            audio.rtcp_xr,
+            Some(RtcpXr {
+                formats: vec![XrFormat::VoipMetrics]
+            })
+        );
+
+        let video = &answer.media[1];
+        assert_eq!(video.payloads, ["96", "97"]);
+        assert_eq!(
+            video.rtcp_fb,
+            [Fb {
+                payload: 96.into(),
+                val: FbVal::GoogRemb
+            }]
+        );
+    }
+
+    #[test]
+    fn answers_directions_and_roles() {
+        let offer = Session::read_str(OFFER);
+        let answer = create_answer(&offer, &local());
+
+        assert_eq!(answer.media[0].direction, Some(Direction::RecvOnly));
+        assert_eq!(answer.media[1].direction, Some(Direction::RecvOnly));
+        assert_eq!(answer.media[0].setup_role, Some(SetupRole::Passive));
+        assert_eq!(answer.media[0].ice.ufrag.as_deref(), Some("local"));
+    }
+
+    #[test]
+    fn rejects_unsupported_media() {
+        let offer = Session::read_str(OFFER);
+        let answer = create_answer(&offer, &local());
+
+        let application = &answer.media[2];
+        assert_eq!(application.port, 0);
+        assert_eq!(application.payloads, ["webrtc-datachannel"]);
+        assert!(application.direction.is_none());
+
+        assert_eq!(
+            answer.groups,
+            [Group {
+                semantics: GroupSemantics::Bundle,
+                mids: vec!["0".into(), "1".into()]
+            }]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "udisplay")]
+    fn answer_is_reparsable() {
+        let offer = Session::read_str(OFFER);
+        let answer = create_answer(&offer, &local());
+        let serialized = answer.to_string();
+        assert_eq!(Session::read_str(&serialized), answer);
+    }
+}
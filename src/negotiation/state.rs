@@ -0,0 +1,411 @@
+//! [JSEP](https://tools.ietf.org/html/rfc8829#section-3.2) signaling state machine
+//!
+//! Keeps track of local and remote descriptions the way `RTCPeerConnection` does,
+//! without doing any networking.
+
+use crate::{
+    attributes::{dtls::SetupRole, Fingerprint, Ice},
+    media_section::MediaSection,
+    Session,
+};
+
+/// `RTCSignalingState`
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub enum SignalingState {
+    #[default]
+    Stable,
+    HaveLocalOffer,
+    HaveRemoteOffer,
+    HaveLocalPranswer,
+    HaveRemotePranswer,
+}
+
+/// `RTCSdpType` without `rollback`, see [`SdpNegotiator::rollback`]
+#[derive(Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub enum SdpType {
+    Offer,
+    Pranswer,
+    Answer,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub enum NegotiationError {
+    /// the description can't be applied in the current state
+    InvalidTransition {
+        state: SignalingState,
+        sdp_type: SdpType,
+        local: bool,
+    },
+    /// rollback is only possible with an offer pending
+    NothingToRollback,
+    /// an answer has to contain exactly as many media sections as the offer
+    MediaMismatch { offered: usize, answered: usize },
+}
+
+/// Applies local and remote descriptions and tracks what has been negotiated.
+#[derive(Default)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct SdpNegotiator<'a> {
+    current_local: Option<Session<'a>>,
+    current_remote: Option<Session<'a>>,
+    pending_local: Option<Session<'a>>,
+    pending_remote: Option<Session<'a>>,
+    state: SignalingState,
+    /// whether the current descriptions were negotiated with us as the offerer
+    local_offered: bool,
+}
+
+impl<'a> SdpNegotiator<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn state(&self) -> SignalingState {
+        self.state
+    }
+
+    pub fn current_local_description(&self) -> Option<&Session<'a>> {
+        self.current_local.as_ref()
+    }
+
+    pub fn current_remote_description(&self) -> Option<&Session<'a>> {
+        self.current_remote.as_ref()
+    }
+
+    pub fn pending_local_description(&self) -> Option<&Session<'a>> {
+        self.pending_local.as_ref()
+    }
+
+    pub fn pending_remote_description(&self) -> Option<&Session<'a>> {
+        self.pending_remote.as_ref()
+    }
+
+    /// the pending description if there is one, otherwise the current one
+    pub fn local_description(&self) -> Option<&Session<'a>> {
+        self.pending_local.as_ref().or(self.current_local.as_ref())
+    }
+
+    /// the pending description if there is one, otherwise the current one
+    pub fn remote_description(&self) -> Option<&Session<'a>> {
+        self.pending_remote
+            .as_ref()
+            .or(self.current_remote.as_ref())
+    }
+
+    pub fn set_local_description(
+        &mut self,
+        sdp_type: SdpType,
+        session: Session<'a>,
+    ) -> Result<SignalingState, NegotiationError> {
+        use SdpType::*;
+        use SignalingState::*;
+        let state = self.state();
+        let next = match (state, sdp_type) {
+            (Stable | HaveLocalOffer, Offer) => HaveLocalOffer,
+            (HaveRemoteOffer | HaveLocalPranswer, Pranswer) => HaveLocalPranswer,
+            (HaveRemoteOffer | HaveLocalPranswer, Answer) => Stable,
+            _ => {
+                return Err(NegotiationError::InvalidTransition {
+                    state,
+                    sdp_type,
+                    local: true,
+                })
+            }
+        };
+        if sdp_type != Offer {
+            check_answer(self.pending_remote.as_ref(), &session)?;
+        }
+
+        if next == Stable {
+            self.current_remote = self.pending_remote.take();
+            self.current_local = Some(session);
+            self.pending_local = None;
+            self.local_offered = false;
+        } else {
+            self.pending_local = Some(session);
+        }
+        self.state = next;
+        Ok(next)
+    }
+
+    pub fn set_remote_description(
+        &mut self,
+        sdp_type: SdpType,
+        session: Session<'a>,
+    ) -> Result<SignalingState, NegotiationError> {
+        use SdpType::*;
+        use SignalingState::*;
+        let state = self.state();
+        let next = match (state, sdp_type) {
+            (Stable | HaveRemoteOffer, Offer) => HaveRemoteOffer,
+            (HaveLocalOffer | HaveRemotePranswer, Pranswer) => HaveRemotePranswer,
+            (HaveLocalOffer | HaveRemotePranswer, Answer) => Stable,
+            _ => {
+                return Err(NegotiationError::InvalidTransition {
+                    state,
+                    sdp_type,
+                    local: false,
+                })
+            }
+        };
+        if sdp_type != Offer {
+            check_answer(self.pending_local.as_ref(), &session)?;
+        }
+
+        if next == Stable {
+            self.current_local = self.pending_local.take();
+            self.current_remote = Some(session);
+            self.pending_remote = None;
+            self.local_offered = true;
+        } else {
+            self.pending_remote = Some(session);
+        }
+        self.state = next;
+        Ok(next)
+    }
+
+    /// Discards a pending offer and returns to [`SignalingState::Stable`].
+    pub fn rollback(&mut self) -> Result<SignalingState, NegotiationError> {
+        match self.state() {
+            SignalingState::HaveLocalOffer | SignalingState::HaveRemoteOffer => {
+                self.pending_local = None;
+                self.pending_remote = None;
+                self.state = SignalingState::Stable;
+                Ok(SignalingState::Stable)
+            }
+            _ => Err(NegotiationError::NothingToRollback),
+        }
+    }
+
+    /// Pairs of local and remote media sections of the current descriptions.
+    pub fn negotiated_media(&self) -> Vec<NegotiatedMedia<'_, 'a>> {
+        let (local, remote) = match (&self.current_local, &self.current_remote) {
+            (Some(local), Some(remote)) => (local, remote),
+            _ => return Vec::new(),
+        };
+        local
+            .media
+            .iter()
+            .zip(remote.media.iter())
+            .map(|(local, remote)| NegotiatedMedia {
+                mid: local.mid.as_deref().or(remote.mid.as_deref()),
+                local,
+                remote,
+                local_offered: self.local_offered,
+            })
+            .collect()
+    }
+
+    /// The currently negotiated media section with the given `a=mid`.
+    pub fn negotiated_media_by_mid(&self, mid: &str) -> Option<NegotiatedMedia<'_, 'a>> {
+        self.negotiated_media()
+            .into_iter()
+            .find(|media| media.mid == Some(mid))
+    }
+}
+
+fn check_answer(offer: Option<&Session>, answer: &Session) -> Result<(), NegotiationError> {
+    match offer {
+        Some(offer) if offer.media.len() != answer.media.len() => {
+            Err(NegotiationError::MediaMismatch {
+                offered: offer.media.len(),
+                answered: answer.media.len(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// One media section as agreed on by both sides.
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct NegotiatedMedia<'s, 'a> {
+    pub mid: Option<&'s str>,
+    pub local: &'s MediaSection<'a>,
+    pub remote: &'s MediaSection<'a>,
+    local_offered: bool,
+}
+
+impl<'s, 'a> NegotiatedMedia<'s, 'a> {
+    /// `m=` line of either side has port `0`
+    pub fn is_rejected(&self) -> bool {
+        self.local.port == 0 || self.remote.port == 0
+    }
+
+    /// payload types listed in the answer, which are the ones both sides agreed on
+    pub fn payloads(&self) -> &'s [std::borrow::Cow<'a, str>] {
+        if self.local_offered {
+            &self.remote.payloads
+        } else {
+            &self.local.payloads
+        }
+    }
+
+    pub fn transport(&self) -> NegotiatedTransport<'s, 'a> {
+        let answer = if self.local_offered {
+            self.remote
+        } else {
+            self.local
+        };
+        // `a=setup` in the answer decides who acts as DTLS client
+        let local_setup_role = match (&answer.setup_role, self.local_offered) {
+            (Some(SetupRole::Active), false) | (Some(SetupRole::Passive), true) => {
+                Some(SetupRole::Active)
+            }
+            (Some(SetupRole::Passive), false) | (Some(SetupRole::Active), true) => {
+                Some(SetupRole::Passive)
+            }
+            _ => None,
+        };
+        NegotiatedTransport {
+            local_ice: &self.local.ice,
+            remote_ice: &self.remote.ice,
+            local_fingerprints: &self.local.fingerprints,
+            remote_fingerprints: &self.remote.fingerprints,
+            local_setup_role,
+        }
+    }
+}
+
+/// ICE and DTLS parameters of a [`NegotiatedMedia`].
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct NegotiatedTransport<'s, 'a> {
+    pub local_ice: &'s Ice<'a>,
+    pub remote_ice: &'s Ice<'a>,
+    pub local_fingerprints: &'s [Fingerprint<'a>],
+    pub remote_fingerprints: &'s [Fingerprint<'a>],
+    /// our DTLS role, either `active` or `passive`
+    pub local_setup_role: Option<SetupRole>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OFFER: &str = "v=0
+o=- 1 1 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 111 0
+a=ice-ufrag:offr
+a=ice-pwd:offerofferofferofferoffer
+a=setup:actpass
+a=mid:0
+a=rtpmap:111 opus/48000/2
+a=rtpmap:0 PCMU/8000
+m=video 9 UDP/TLS/RTP/SAVPF 96
+a=ice-ufrag:offr
+a=ice-pwd:offerofferofferofferoffer
+a=setup:actpass
+a=mid:1
+a=rtpmap:96 VP8/90000
+";
+
+    const ANSWER: &str = "v=0
+o=- 2 1 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=ice-ufrag:answ
+a=ice-pwd:answeransweransweranswer
+a=setup:active
+a=mid:0
+a=rtpmap:111 opus/48000/2
+m=video 0 UDP/TLS/RTP/SAVPF 96
+a=mid:1
+";
+
+    #[test]
+    fn local_offer_remote_answer() {
+        let mut negotiator = SdpNegotiator::new();
+        assert_eq!(negotiator.state(), SignalingState::Stable);
+
+        let state = negotiator.set_local_description(SdpType::Offer, Session::read_str(OFFER));
+        assert_eq!(state, Ok(SignalingState::HaveLocalOffer));
+        assert!(negotiator.negotiated_media().is_empty());
+
+        let state = negotiator.set_remote_description(SdpType::Answer, Session::read_str(ANSWER));
+        assert_eq!(state, Ok(SignalingState::Stable));
+        assert!(negotiator.pending_local_description().is_none());
+
+        let audio = negotiator.negotiated_media_by_mid("0").unwrap();
+        assert!(!audio.is_rejected());
+        assert_eq!(audio.payloads(), ["111"]);
+        let transport = audio.transport();
+        assert_eq!(transport.remote_ice.ufrag.as_deref(), Some("answ"));
+        assert_eq!(transport.local_setup_role, Some(SetupRole::Passive));
+
+        assert!(negotiator
+            .negotiated_media_by_mid("1")
+            .unwrap()
+            .is_rejected());
+    }
+
+    #[test]
+    fn remote_offer_with_pranswer() {
+        let mut negotiator = SdpNegotiator::new();
+        negotiator
+            .set_remote_description(SdpType::Offer, Session::read_str(OFFER))
+            .unwrap();
+        let state = negotiator.set_local_description(SdpType::Pranswer, Session::read_str(ANSWER));
+        assert_eq!(state, Ok(SignalingState::HaveLocalPranswer));
+        let state = negotiator.set_local_description(SdpType::Answer, Session::read_str(ANSWER));
+        assert_eq!(state, Ok(SignalingState::Stable));
+
+        let audio = negotiator.negotiated_media_by_mid("0").unwrap();
+        assert_eq!(audio.transport().local_setup_role, Some(SetupRole::Active));
+    }
+
+    #[test]
+    fn rejects_invalid_transitions() {
+        let mut negotiator = SdpNegotiator::new();
+        assert_eq!(
+            negotiator.set_remote_description(SdpType::Answer, Session::read_str(ANSWER)),
+            Err(NegotiationError::InvalidTransition {
+                state: SignalingState::Stable,
+                sdp_type: SdpType::Answer,
+                local: false
+            })
+        );
+        negotiator
+            .set_local_description(SdpType::Offer, Session::read_str(OFFER))
+            .unwrap();
+        assert!(negotiator
+            .set_remote_description(SdpType::Offer, Session::read_str(OFFER))
+            .is_err());
+        assert_eq!(
+            negotiator.set_remote_description(SdpType::Answer, Session::read_str("v=0")),
+            Err(NegotiationError::MediaMismatch {
+                offered: 2,
+                answered: 0
+            })
+        );
+        assert_eq!(negotiator.state(), SignalingState::HaveLocalOffer);
+    }
+
+    #[test]
+    fn rollback() {
+        let mut negotiator = SdpNegotiator::new();
+        assert_eq!(
+            negotiator.rollback(),
+            Err(NegotiationError::NothingToRollback)
+        );
+        negotiator
+            .set_remote_description(SdpType::Offer, Session::read_str(OFFER))
+            .unwrap();
+        assert_eq!(negotiator.rollback(), Ok(SignalingState::Stable));
+        assert!(negotiator.remote_description().is_none());
+    }
+}
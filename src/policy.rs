@@ -0,0 +1,231 @@
+//! Candidate filtering and address rewriting
+//!
+//! Media relays usually only want to expose some of the candidates they gather
+//! and have to replace internal addresses by public ones before handing out an SDP.
+
+use std::net::IpAddr;
+
+use crate::{
+    attributes::{Candidate, CandidateAddress, CandidateProtocol, CandidateType},
+    media_section::MediaSection,
+    parsers::IpVer,
+    Session,
+};
+
+/// Which candidates to drop, a candidate is dropped if any of the criteria matches.
+#[derive(Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct CandidateFilter {
+    pub types: Vec<CandidateType>,
+    pub protocols: Vec<CandidateProtocol>,
+    pub ipv4: bool,
+    pub ipv6: bool,
+    /// `10/8`, `172.16/12`, `192.168/16` and `fc00::/7`
+    pub private: bool,
+    /// `127/8` and `::1`
+    pub loopback: bool,
+    /// `169.254/16` and `fe80::/10`
+    pub link_local: bool,
+    /// `.local` names
+    pub mdns: bool,
+}
+
+fn is_private(addr: &IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(addr) => addr.is_private(),
+        IpAddr::V6(addr) => addr.segments()[0] & 0xfe00 == 0xfc00,
+    }
+}
+
+fn is_link_local(addr: &IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(addr) => addr.is_link_local(),
+        IpAddr::V6(addr) => addr.segments()[0] & 0xffc0 == 0xfe80,
+    }
+}
+
+fn ip_ver(addr: &IpAddr) -> IpVer {
+    match addr {
+        IpAddr::V4(_) => IpVer::Ip4,
+        IpAddr::V6(_) => IpVer::Ip6,
+    }
+}
+
+impl CandidateFilter {
+    /// Whether `candidate` should be dropped.
+    pub fn matches(&self, candidate: &Candidate) -> bool {
+        if self.types.contains(&candidate.r#type) || self.protocols.contains(&candidate.protocol) {
+            return true;
+        }
+        match &candidate.addr {
+            CandidateAddress::Ip(addr) => {
+                (self.ipv4 && addr.is_ipv4())
+                    || (self.ipv6 && addr.is_ipv6())
+                    || (self.private && is_private(addr))
+                    || (self.loopback && addr.is_loopback())
+                    || (self.link_local && is_link_local(addr))
+            }
+            fqdn => self.mdns && fqdn.is_mdns(),
+        }
+    }
+}
+
+impl MediaSection<'_> {
+    /// Drops all candidates matching `filter` and returns how many were dropped.
+    ///
+    /// `a=end-of-candidates` is kept, gathering is still complete even if nothing is left.
+    pub fn filter_candidates(&mut self, filter: &CandidateFilter) -> usize {
+        let before = self.candidates.len();
+        self.candidates
+            .retain(|candidate| !filter.matches(candidate));
+        before - self.candidates.len()
+    }
+
+    /// Replaces the addresses of candidates, `c=` and `a=rtcp` by what `map` returns for them.
+    ///
+    /// Addresses `map` returns `None` for are left alone.
+    pub fn rewrite_addresses(&mut self, mut map: impl FnMut(IpAddr) -> Option<IpAddr>) {
+        if let Some(connection) = self.connection.as_mut() {
+            if let Some(addr) = map(connection.addr) {
+                connection.ip_ver = ip_ver(&addr);
+                connection.addr = addr;
+            }
+        }
+        if let Some(address) = self.rtcp.as_mut().and_then(|rtcp| rtcp.address.as_mut()) {
+            if let Some(addr) = address.addr.as_ip().and_then(&mut map) {
+                address.ip_ver = ip_ver(&addr);
+                address.addr = addr.into();
+            }
+        }
+        for candidate in &mut self.candidates {
+            let addresses = std::iter::once(&mut candidate.addr).chain(candidate.raddr.as_mut());
+            for address in addresses {
+                if let Some(addr) = address.as_ip().and_then(&mut map) {
+                    *address = addr.into();
+                }
+            }
+        }
+    }
+}
+
+impl Session<'_> {
+    /// Drops all candidates matching `filter` from every media section,
+    /// see [`MediaSection::filter_candidates`].
+    pub fn filter_candidates(&mut self, filter: &CandidateFilter) -> usize {
+        self.media
+            .iter_mut()
+            .map(|media| media.filter_candidates(filter))
+            .sum()
+    }
+
+    /// Replaces the addresses of `o=`, `c=`, and of every media section,
+    /// see [`MediaSection::rewrite_addresses`].
+    pub fn rewrite_addresses(&mut self, mut map: impl FnMut(IpAddr) -> Option<IpAddr>) {
+        if let Some(origin) = self.origin.as_mut() {
+            if let Some(addr) = map(origin.addr) {
+                origin.ip_ver = ip_ver(&addr);
+                origin.addr = addr;
+            }
+        }
+        if let Some(connection) = self.connection.as_mut() {
+            if let Some(addr) = map(connection.addr) {
+                connection.ip_ver = ip_ver(&addr);
+                connection.addr = addr;
+            }
+        }
+        for media in &mut self.media {
+            media.rewrite_addresses(&mut map);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::attributes::AttributeLine;
+
+    const SDP: &str = "v=0
+o=- 1 1 IN IP4 10.0.0.1
+s=-
+t=0 0
+c=IN IP4 10.0.0.1
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+c=IN IP4 10.0.0.1
+a=rtcp:9 IN IP4 10.0.0.1
+a=candidate:1 1 udp 2122260223 10.0.0.1 46243 typ host
+a=candidate:2 1 tcp 1518280447 10.0.0.1 9 typ host tcptype active
+a=candidate:3 1 udp 2122262783 9f3c1a2e.local 54321 typ host
+a=candidate:4 1 udp 2122262783 fe80::1 54321 typ host
+a=candidate:5 1 udp 1686052607 203.0.113.7 36768 typ srflx raddr 10.0.0.1 rport 46243
+a=candidate:6 1 udp 41885439 198.51.100.1 50000 typ relay raddr 203.0.113.7 rport 36768
+a=candidate:7 1 udp 2122260223 127.0.0.1 46243 typ host
+a=end-of-candidates
+";
+
+    fn foundations(session: &Session) -> Vec<String> {
+        session.media[0]
+            .candidates
+            .iter()
+            .map(|c| c.foundation.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn filters_candidates() {
+        let mut session = Session::read_str(SDP);
+        let dropped = session.filter_candidates(&CandidateFilter {
+            protocols: vec![CandidateProtocol::Tcp],
+            mdns: true,
+            ..Default::default()
+        });
+        assert_eq!(dropped, 2);
+        assert_eq!(foundations(&session), ["1", "4", "5", "6", "7"]);
+
+        session.filter_candidates(&CandidateFilter {
+            private: true,
+            loopback: true,
+            link_local: true,
+            ..Default::default()
+        });
+        assert_eq!(foundations(&session), ["5", "6"]);
+
+        session.filter_candidates(&CandidateFilter {
+            types: vec![CandidateType::Srflx],
+            ..Default::default()
+        });
+        assert_eq!(foundations(&session), ["6"]);
+
+        session.filter_candidates(&CandidateFilter {
+            ipv4: true,
+            ..Default::default()
+        });
+        assert!(session.media[0].candidates.is_empty());
+        assert!(session.media[0].attributes.contains(&AttributeLine::EoC));
+    }
+
+    #[test]
+    fn rewrites_addresses() {
+        let mut session = Session::read_str(SDP);
+        let public = IpAddr::from(Ipv4Addr::new(203, 0, 113, 1));
+        let private = IpAddr::from(Ipv4Addr::new(10, 0, 0, 1));
+        session.rewrite_addresses(|addr| if addr == private { Some(public) } else { None });
+
+        assert_eq!(session.origin.as_ref().unwrap().addr, public);
+        assert_eq!(session.connection.as_ref().unwrap().addr, public);
+        let media = &session.media[0];
+        assert_eq!(media.connection.as_ref().unwrap().addr, public);
+        assert_eq!(
+            media.rtcp.as_ref().unwrap().address.as_ref().unwrap().addr,
+            CandidateAddress::Ip(public)
+        );
+        assert_eq!(media.candidates[0].addr, CandidateAddress::Ip(public));
+        assert_eq!(
+            media.candidates[4].raddr,
+            Some(CandidateAddress::Ip(public))
+        );
+        assert!(media.candidates[2].is_mdns());
+        assert!(media.attributes.contains(&AttributeLine::EoC));
+    }
+}
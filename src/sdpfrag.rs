@@ -0,0 +1,203 @@
+//! Trickle ICE SDP fragments
+//!
+//! `application/trickle-ice-sdpfrag` bodies carry ICE parameters and candidates for
+//! media sections identified by `a=mid`.
+//!
+//! read [RFC8840 Section 9](https://tools.ietf.org/html/rfc8840#section-9)
+
+use std::borrow::Cow;
+
+use derive_into_owned::IntoOwned;
+
+use crate::{
+    attributes::{candidate::Candidate, ice::IceParameter, AttributeLine, Ice},
+    lines::{media::Media, SessionLine},
+    sdp_line, SdpLine, Session,
+};
+
+/// The part of an sdpfrag that belongs to one media section
+#[derive(Clone, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct SdpFragMedia<'a> {
+    /// usually a dummy like `m=audio 9 RTP/AVP 0`
+    pub media: Media<'a>,
+    pub mid: Option<Cow<'a, str>>,
+    pub ice: Ice<'a>,
+    pub candidates: Vec<Candidate<'a>>,
+    pub end_of_candidates: bool,
+}
+
+impl<'a> From<Media<'a>> for SdpFragMedia<'a> {
+    fn from(media: Media<'a>) -> Self {
+        SdpFragMedia {
+            media,
+            mid: None,
+            ice: Ice::default(),
+            candidates: Vec::new(),
+            end_of_candidates: false,
+        }
+    }
+}
+
+/// `application/trickle-ice-sdpfrag`
+#[derive(Clone, Default, IntoOwned, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct SdpFrag<'a> {
+    /// ICE parameters that apply to all media sections
+    pub ice: Ice<'a>,
+    /// `a=end-of-candidates` at session level applies to all media sections
+    pub end_of_candidates: bool,
+    pub media: Vec<SdpFragMedia<'a>>,
+}
+
+fn add_ice_parameter<'a>(ice: &mut Ice<'a>, parameter: IceParameter<'a>) {
+    match parameter {
+        IceParameter::Ufrag(ufrag) => ice.ufrag = Some(ufrag),
+        IceParameter::Pwd(pwd) => ice.pwd = Some(pwd),
+        IceParameter::Options(options) => ice.options = Some(options),
+        _ => {}
+    }
+}
+
+impl<'a> SdpFrag<'a> {
+    /// Parses an sdpfrag, lines that aren't part of one are skipped.
+    pub fn read_str(sdpfrag: &'a str) -> SdpFrag<'a> {
+        let mut frag = SdpFrag::default();
+        for line in sdpfrag.lines().filter_map(|line| sdp_line(line).ok()) {
+            match (line.1, frag.media.last_mut()) {
+                (SdpLine::Session(SessionLine::Media(media)), _) => frag.media.push(media.into()),
+                (SdpLine::Attribute(AttributeLine::Ice(parameter)), None) => {
+                    add_ice_parameter(&mut frag.ice, parameter)
+                }
+                (SdpLine::Attribute(AttributeLine::EoC), None) => frag.end_of_candidates = true,
+                (SdpLine::Attribute(AttributeLine::Ice(parameter)), Some(media)) => {
+                    add_ice_parameter(&mut media.ice, parameter)
+                }
+                (SdpLine::Attribute(AttributeLine::Mid(mid)), Some(media)) => {
+                    media.mid = Some(mid.0)
+                }
+                (SdpLine::Attribute(AttributeLine::Candidate(candidate)), Some(media)) => {
+                    media.candidates.push(candidate)
+                }
+                (SdpLine::Attribute(AttributeLine::EoC), Some(media)) => {
+                    media.end_of_candidates = true
+                }
+                _ => {}
+            }
+        }
+        frag
+    }
+}
+
+impl<'a> Session<'a> {
+    /// Adds the candidates of an sdpfrag to the media sections with the same `a=mid`.
+    ///
+    /// Fragments for a different `a=ice-ufrag` belong to another ICE generation and are ignored,
+    /// as are candidates that are already known.
+    /// Returns the number of candidates that were added.
+    pub fn merge_sdpfrag(&mut self, frag: SdpFrag<'a>) -> usize {
+        let mut added = 0;
+        let session_ufrag = self.ice.ufrag.clone();
+        for frag_media in frag.media {
+            let media = match frag_media.mid.as_deref().and_then(|mid| {
+                self.media
+                    .iter_mut()
+                    .find(|m| m.mid.as_deref() == Some(mid))
+            }) {
+                Some(media) => media,
+                None => continue,
+            };
+
+            let ufrag = frag_media.ice.ufrag.as_ref().or(frag.ice.ufrag.as_ref());
+            let current_ufrag = media.ice.ufrag.as_ref().or(session_ufrag.as_ref());
+            if ufrag.is_some() && current_ufrag.is_some() && ufrag != current_ufrag {
+                continue;
+            }
+
+            for candidate in frag_media.candidates {
+                if !media.candidates.contains(&candidate) {
+                    media.candidates.push(candidate);
+                    added += 1;
+                }
+            }
+            if (frag_media.end_of_candidates || frag.end_of_candidates)
+                && !media.attributes.contains(&AttributeLine::EoC)
+            {
+                media.attributes.push(AttributeLine::EoC);
+            }
+        }
+        added
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attributes::IceOption;
+
+    const SDPFRAG: &str = "a=ice-options:trickle
+m=audio 9 RTP/AVP 0
+a=mid:a1
+a=ice-ufrag:ETEn1v9DoTMB9J4r
+a=ice-pwd:OtSK0WpNtpUjkY4+86js7ZQl
+a=candidate:1 1 udp 2122260223 192.168.0.196 46243 typ host
+a=candidate:2 1 udp 2122262783 9f3c1a2e.local 54321 typ host
+a=end-of-candidates
+m=video 9 RTP/AVP 0
+a=mid:v1
+a=ice-ufrag:other
+a=candidate:3 1 udp 2122260223 192.168.0.196 46244 typ host
+";
+
+    #[test]
+    fn parses_sdpfrag() {
+        let frag = SdpFrag::read_str(SDPFRAG);
+        assert!(frag.ice.has_option(&IceOption::Trickle));
+        assert!(!frag.end_of_candidates);
+        assert_eq!(frag.media.len(), 2);
+        assert_eq!(frag.media[0].mid.as_deref(), Some("a1"));
+        assert_eq!(frag.media[0].ice.ufrag.as_deref(), Some("ETEn1v9DoTMB9J4r"));
+        assert_eq!(frag.media[0].candidates.len(), 2);
+        assert!(frag.media[0].end_of_candidates);
+        assert!(!frag.media[1].end_of_candidates);
+    }
+
+    #[test]
+    #[cfg(feature = "udisplay")]
+    fn prints_sdpfrag() {
+        let frag = SdpFrag::read_str(SDPFRAG);
+        assert_eq!(crate::ufmt_to_string(&frag), SDPFRAG);
+    }
+
+    #[test]
+    fn merges_into_session() {
+        let fixture = std::fs::read_to_string(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("fixtures/sdp_transform/jsep.sdp"),
+        )
+        .unwrap();
+        let mut session = Session::read_str(&fixture);
+        session.media[0]
+            .attributes
+            .retain(|a| *a != AttributeLine::EoC);
+        let known = session.media[0].candidates.len();
+
+        let added = session.merge_sdpfrag(SdpFrag::read_str(SDPFRAG));
+        assert_eq!(added, 2);
+        assert_eq!(session.media[0].candidates.len(), known + 2);
+        assert!(session.media[0].attributes.contains(&AttributeLine::EoC));
+        assert!(session.media[1].candidates.is_empty());
+
+        assert_eq!(session.merge_sdpfrag(SdpFrag::read_str(SDPFRAG)), 0);
+    }
+}
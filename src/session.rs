@@ -1,7 +1,14 @@
+use std::net::IpAddr;
+
 use derive_into_owned::IntoOwned;
 
 use crate::{
-    attributes::AttributeLine,
+    attributes::{
+        dtls::{ConnectionMode, SetupRole},
+        ice::IceParameter,
+        msid::MsidSemantic,
+        AttributeLine, Fingerprint, Group, Ice,
+    },
     lines::{
         bandwidth::BandWidth, connection::Connection, email::EmailAddress, origin::Origin,
         phone_number::PhoneNumber, session_information::SessionInformation,
@@ -11,7 +18,16 @@ use crate::{
     sdp_line, SdpLine,
 };
 
-#[derive(Default, IntoOwned, PartialEq, Eq)]
+pub mod bundle;
+pub mod changes;
+pub mod inheritance;
+pub mod plan_b;
+
+pub use bundle::{BundleTransport, Transport};
+pub use changes::SessionChanges;
+pub use inheritance::ResolvedMedia;
+
+#[derive(Default, IntoOwned)]
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[cfg_attr(
     feature = "serde",
@@ -48,10 +64,163 @@ pub struct Session<'a> {
 
     pub description: Option<SessionInformation<'a>>,
 
+    /// `a=group:BUNDLE 0 1`, `a=group:LS 1 2`
+    pub groups: Vec<Group<'a>>,
+
+    /// `a=extmap-allow-mixed`
+    pub extmap_allow_mixed: bool,
+
+    /// `a=cryptex`
+    pub cryptex: bool,
+
+    /// `a=msid-semantic: WMS *`
+    pub msid_semantic: Option<MsidSemantic<'a>>,
+
+    /// `a=ice-lite`
+    pub ice_lite: bool,
+
+    /// `a=ice-pacing:50`
+    pub ice_pacing: Option<u32>,
+
+    /// `a=ice-ufrag`, `a=ice-pwd` and `a=ice-options`
+    pub ice: Ice<'a>,
+
+    /// `a=fingerprint:sha-256 19:E2:1C:3B:4B:9F:81:E6:B8:5C:F4:A5:A8:D8:73:04:BB:05:2F:70:9F:04:A9:0E:05:E9:26:33:E8:70:88:A2`
+    /// one line per certificate and hash function
+    pub fingerprints: Vec<Fingerprint<'a>>,
+
+    /// `a=setup:actpass`
+    pub setup_role: Option<SetupRole>,
+
+    /// `a=connection:new|existing`
+    pub dtls_connection: Option<ConnectionMode>,
+
     pub attributes: Vec<AttributeLine<'a>>,
+
+    /// the order session-level attribute lines were read in, they are printed in the same order
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) attribute_order: Vec<SessionAttribute>,
+
     pub media: Vec<MediaSection<'a>>,
 }
 
+/// Sessions that only differ in the order of their session-level attribute lines are equal.
+impl PartialEq for Session<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        let Session {
+            version,
+            name,
+            timing,
+            origin,
+            band_width,
+            uri,
+            phone_number,
+            email_address,
+            connection,
+            description,
+            groups,
+            extmap_allow_mixed,
+            cryptex,
+            msid_semantic,
+            ice_lite,
+            ice_pacing,
+            ice,
+            fingerprints,
+            setup_role,
+            dtls_connection,
+            attributes,
+            attribute_order: _,
+            media,
+        } = self;
+        *version == other.version
+            && *name == other.name
+            && *timing == other.timing
+            && *origin == other.origin
+            && *band_width == other.band_width
+            && *uri == other.uri
+            && *phone_number == other.phone_number
+            && *email_address == other.email_address
+            && *connection == other.connection
+            && *description == other.description
+            && *groups == other.groups
+            && *extmap_allow_mixed == other.extmap_allow_mixed
+            && *cryptex == other.cryptex
+            && *msid_semantic == other.msid_semantic
+            && *ice_lite == other.ice_lite
+            && *ice_pacing == other.ice_pacing
+            && *ice == other.ice
+            && *fingerprints == other.fingerprints
+            && *setup_role == other.setup_role
+            && *dtls_connection == other.dtls_connection
+            && *attributes == other.attributes
+            && *media == other.media
+    }
+}
+
+impl Eq for Session<'_> {}
+
+/// Which field of [`Session`] a session-level attribute line was read into
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub(crate) enum SessionAttribute {
+    Group,
+    ExtmapAllowMixed,
+    Cryptex,
+    MsidSemantic,
+    IceLite,
+    IcePacing,
+    IceUfrag,
+    IcePwd,
+    IceOptions,
+    Fingerprint,
+    Setup,
+    DtlsConnection,
+    /// a line in [`Session::attributes`]
+    Other,
+}
+
+impl SessionAttribute {
+    /// the order lines are printed in if they weren't read from a description
+    pub(crate) const DEFAULT_ORDER: [SessionAttribute; 13] = [
+        SessionAttribute::Group,
+        SessionAttribute::ExtmapAllowMixed,
+        SessionAttribute::Cryptex,
+        SessionAttribute::MsidSemantic,
+        SessionAttribute::IceLite,
+        SessionAttribute::IcePacing,
+        SessionAttribute::IceUfrag,
+        SessionAttribute::IcePwd,
+        SessionAttribute::IceOptions,
+        SessionAttribute::Fingerprint,
+        SessionAttribute::Setup,
+        SessionAttribute::DtlsConnection,
+        SessionAttribute::Other,
+    ];
+
+    fn of(attribute: &AttributeLine) -> Self {
+        match attribute {
+            AttributeLine::Group(_) => SessionAttribute::Group,
+            AttributeLine::ExtmapAllowMixed => SessionAttribute::ExtmapAllowMixed,
+            AttributeLine::Cryptex => SessionAttribute::Cryptex,
+            AttributeLine::MsidSemantic(_) => SessionAttribute::MsidSemantic,
+            AttributeLine::Ice(IceParameter::Lite) => SessionAttribute::IceLite,
+            AttributeLine::Ice(IceParameter::Pacing(_)) => SessionAttribute::IcePacing,
+            AttributeLine::Ice(IceParameter::Ufrag(_)) => SessionAttribute::IceUfrag,
+            AttributeLine::Ice(IceParameter::Pwd(_)) => SessionAttribute::IcePwd,
+            AttributeLine::Ice(IceParameter::Options(_)) => SessionAttribute::IceOptions,
+            AttributeLine::Fingerprint(_) => SessionAttribute::Fingerprint,
+            AttributeLine::SetupRole(_) => SessionAttribute::Setup,
+            AttributeLine::DtlsConnection(_) => SessionAttribute::DtlsConnection,
+            _ => SessionAttribute::Other,
+        }
+    }
+}
+
 type ParseError<'a> = nom::Err<nom::error::Error<&'a str>>;
 
 #[derive(Default)]
@@ -65,6 +234,9 @@ struct ParserState<'a> {
 impl<'a> Session<'a> {
     fn add_line(&mut self, line: SdpLine<'a>) {
         use SessionLine::*;
+        if let SdpLine::Attribute(attribute) = &line {
+            self.attribute_order.push(SessionAttribute::of(attribute));
+        }
         match line {
             //crate::SdpLine::Session(Session)       => todo!(),
             SdpLine::Session(Version(version)) => self.version = Some(version),
@@ -78,6 +250,30 @@ impl<'a> Session<'a> {
             SdpLine::Session(Connection(connection)) => self.connection = Some(connection),
             SdpLine::Session(Description(info)) => self.description = Some(info),
             SdpLine::Session(Media(_)) => unreachable!(),
+            SdpLine::Attribute(AttributeLine::Group(group)) => self.groups.push(group),
+            SdpLine::Attribute(AttributeLine::ExtmapAllowMixed) => self.extmap_allow_mixed = true,
+            SdpLine::Attribute(AttributeLine::Cryptex) => self.cryptex = true,
+            SdpLine::Attribute(AttributeLine::MsidSemantic(semantic)) => {
+                self.msid_semantic = Some(semantic)
+            }
+            SdpLine::Attribute(AttributeLine::Ice(IceParameter::Lite)) => self.ice_lite = true,
+            SdpLine::Attribute(AttributeLine::Ice(IceParameter::Pacing(pacing))) => {
+                self.ice_pacing = Some(pacing)
+            }
+            SdpLine::Attribute(AttributeLine::Ice(IceParameter::Ufrag(o))) => {
+                self.ice.ufrag = Some(o)
+            }
+            SdpLine::Attribute(AttributeLine::Ice(IceParameter::Pwd(o))) => self.ice.pwd = Some(o),
+            SdpLine::Attribute(AttributeLine::Ice(IceParameter::Options(o))) => {
+                self.ice.options = Some(o)
+            }
+            SdpLine::Attribute(AttributeLine::Fingerprint(fingerprint)) => {
+                self.fingerprints.push(fingerprint)
+            }
+            SdpLine::Attribute(AttributeLine::SetupRole(role)) => self.setup_role = Some(role),
+            SdpLine::Attribute(AttributeLine::DtlsConnection(mode)) => {
+                self.dtls_connection = Some(mode)
+            }
             SdpLine::Attribute(a) => self.attributes.push(a),
             SdpLine::Comment(_) => {}
         }
@@ -126,6 +322,21 @@ impl<'a> Session<'a> {
         Self::try_from(sdp, false).expect("unfallible should mean this never unwraps")
     }
 
+    /// `a=group:BUNDLE` lines
+    pub fn bundle_groups(&self) -> impl Iterator<Item = &Group<'a>> {
+        self.groups.iter().filter(|group| group.is_bundle())
+    }
+
+    /// All groups the media section with this `a=mid` is part of.
+    pub fn groups_for_mid<'s>(&'s self, mid: &'s str) -> impl Iterator<Item = &'s Group<'a>> {
+        self.groups.iter().filter(move |group| group.contains(mid))
+    }
+
+    /// The `BUNDLE` group this `a=mid` is part of, there can only be one.
+    pub fn bundle_group_for_mid(&self, mid: &str) -> Option<&Group<'a>> {
+        self.bundle_groups().find(|group| group.contains(mid))
+    }
+
     pub fn modify_media<F>(mut self, f: F) -> Self
     where
         F: Fn(MediaSection) -> MediaSection,
@@ -133,6 +344,15 @@ impl<'a> Session<'a> {
         self.media = self.media.into_iter().map(f).collect();
         self
     }
+
+    /// Substitutes mDNS and other names in all candidates, see [`Candidate::resolve_addresses`].
+    ///
+    /// [`Candidate::resolve_addresses`]: crate::attributes::Candidate::resolve_addresses
+    pub fn resolve_candidate_addresses(&mut self, mut lookup: impl FnMut(&str) -> Option<IpAddr>) {
+        for candidate in self.media.iter_mut().flat_map(|m| m.candidates.iter_mut()) {
+            candidate.resolve_addresses(&mut lookup);
+        }
+    }
 }
 
 #[cfg(all(feature = "udisplay", not(feature = "display")))]
@@ -150,3 +370,103 @@ pub fn ufmt_to_string<U: ufmt::uDisplay>(stuff: &U) -> String {
     ufmt::uwrite!(output, "{}", stuff).unwrap();
     output
 }
+
+#[test]
+fn test_session_level_attributes() {
+    let session = Session::read_str(
+        "v=0
+a=group:BUNDLE 0 1
+a=group:BUNDLE 2
+a=group:LS 0 2
+a=extmap-allow-mixed
+a=msid-semantic: WMS lgsCFqt9kN2fVKw5wg3NKqGdATQoltEwOdMS
+a=ice-lite
+a=ice-pacing:50
+a=ice-options:trickle
+a=fingerprint:sha-256 19:E2:1C:3B:4B:9F:81:E6:B8:5C:F4:A5:A8:D8:73:04:BB:05:2F:70:9F:04:A9:0E:05:E9:26:33:E8:70:88:A2
+a=setup:actpass
+a=connection:new
+m=audio 9 UDP/TLS/RTP/SAVPF 111",
+    );
+    assert_eq!(session.groups.len(), 3);
+    assert_eq!(session.bundle_groups().count(), 2);
+    assert_eq!(session.groups_for_mid("2").count(), 2);
+    assert_eq!(
+        session
+            .bundle_group_for_mid("1")
+            .map(|group| group.mids.len()),
+        Some(2)
+    );
+    assert!(session.bundle_group_for_mid("3").is_none());
+    assert!(session.extmap_allow_mixed);
+    assert!(session.msid_semantic.is_some());
+    assert!(session.ice_lite);
+    assert_eq!(session.ice_pacing, Some(50));
+    assert!(session.ice.options.as_ref().unwrap().trickle());
+    assert_eq!(session.fingerprints[0].r#type, "sha-256");
+    assert_eq!(session.setup_role, Some(SetupRole::ActPass));
+    assert_eq!(session.dtls_connection, Some(ConnectionMode::New));
+    assert!(session.attributes.is_empty());
+}
+
+#[test]
+fn test_multiple_fingerprints() {
+    let sdp = "v=0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=fingerprint:sha-1 4A:AD:B9:B1:3F:82:18:3B:54:02:12:DF:3E:5D:49:6B:19:E5:7C:AB
+a=fingerprint:sha-256 19:E2:1C:3B:4B:9F:81:E6:B8:5C:F4:A5:A8:D8:73:04:BB:05:2F:70:9F:04:A9:0E:05:E9:26:33:E8:70:88:A2
+";
+    let session = Session::read_str(sdp);
+    let algorithms = session.media[0]
+        .fingerprints
+        .iter()
+        .map(|f| f.algorithm())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        algorithms,
+        [
+            crate::attributes::HashAlgorithm::Sha1,
+            crate::attributes::HashAlgorithm::Sha256
+        ]
+    );
+    assert!(session.media[0].fingerprints.iter().all(|f| f.is_valid()));
+    #[cfg(all(feature = "udisplay", not(feature = "display")))]
+    assert_eq!(session.to_string(), sdp);
+}
+
+#[test]
+#[cfg(all(feature = "udisplay", not(feature = "display")))]
+fn test_session_attribute_order() {
+    let sdp = "v=0
+a=fingerprint:sha-256 19:E2:1C:3B:4B:9F:81:E6:B8:5C:F4:A5:A8:D8:73:04:BB:05:2F:70:9F:04:A9:0E:05:E9:26:33:E8:70:88:A2
+a=sendrecv
+a=group:BUNDLE 0
+a=ice-options:trickle
+a=group:LS 0
+";
+    let mut session = Session::read_str(sdp);
+    assert_eq!(session.to_string(), sdp);
+
+    let reordered = "v=0
+a=group:BUNDLE 0
+a=sendrecv
+a=ice-options:trickle
+a=group:LS 0
+a=fingerprint:sha-256 19:E2:1C:3B:4B:9F:81:E6:B8:5C:F4:A5:A8:D8:73:04:BB:05:2F:70:9F:04:A9:0E:05:E9:26:33:E8:70:88:A2
+";
+    assert!(session == Session::read_str(reordered));
+    assert_ne!(session.to_string(), reordered);
+
+    session.groups.remove(0);
+    session.setup_role = Some(SetupRole::ActPass);
+    assert_eq!(
+        session.to_string(),
+        "v=0
+a=fingerprint:sha-256 19:E2:1C:3B:4B:9F:81:E6:B8:5C:F4:A5:A8:D8:73:04:BB:05:2F:70:9F:04:A9:0E:05:E9:26:33:E8:70:88:A2
+a=sendrecv
+a=group:LS 0
+a=ice-options:trickle
+a=setup:actpass
+"
+    );
+}
@@ -0,0 +1,241 @@
+//! [BUNDLE](https://tools.ietf.org/html/rfc8843) transport resolution
+//!
+//! All media sections of a `a=group:BUNDLE` share the transport of the tagged media section,
+//! which is the first one in the group that isn't rejected or `a=bundle-only`.
+
+use std::borrow::Cow;
+
+use crate::{
+    attributes::{
+        dtls::{ConnectionMode, SetupRole, TlsId},
+        AttributeLine, Candidate, Fingerprint, Group, IceOptions,
+    },
+    lines::connection::Connection,
+    media_section::MediaSection,
+    Session,
+};
+
+/// A `BUNDLE` group together with the media section carrying its transport.
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct BundleTransport<'s, 'a> {
+    pub group: &'s Group<'a>,
+    pub tagged: &'s MediaSection<'a>,
+}
+
+impl<'s, 'a> BundleTransport<'s, 'a> {
+    pub fn tagged_mid(&self) -> Option<&'s str> {
+        self.tagged.mid.as_deref()
+    }
+
+    /// every media section that shares the tagged transport, including the tagged one
+    pub fn mids(&self) -> impl Iterator<Item = &'s str> {
+        self.group.mids.iter().map(AsRef::as_ref)
+    }
+}
+
+/// The ICE and DTLS parameters that apply to a media section.
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Transport<'s, 'a> {
+    /// the `a=mid` of the media section the parameters were taken from
+    pub mid: Option<&'s str>,
+    pub port: u32,
+    pub connection: Option<&'s Connection>,
+    pub ice_ufrag: Option<&'s Cow<'a, str>>,
+    pub ice_pwd: Option<&'s Cow<'a, str>>,
+    pub ice_options: Option<&'s IceOptions<'a>>,
+    pub fingerprints: &'s [Fingerprint<'a>],
+    pub setup_role: Option<&'s SetupRole>,
+    pub dtls_connection: Option<&'s ConnectionMode>,
+    pub tls_id: Option<&'s TlsId<'a>>,
+    pub candidates: &'s [Candidate<'a>],
+}
+
+impl<'a> Session<'a> {
+    pub fn media_by_mid(&self, mid: &str) -> Option<&MediaSection<'a>> {
+        self.media.iter().find(|m| m.mid.as_deref() == Some(mid))
+    }
+
+    fn tagged_section(&self, group: &Group<'a>) -> Option<&MediaSection<'a>> {
+        group
+            .mids
+            .iter()
+            .filter_map(|mid| self.media_by_mid(mid))
+            .find(|m| m.port != 0 && !m.bundle_only)
+    }
+
+    /// Every `BUNDLE` group that has a tagged media section.
+    pub fn bundle_transports(&self) -> Vec<BundleTransport<'_, 'a>> {
+        self.bundle_groups()
+            .filter_map(|group| {
+                self.tagged_section(group)
+                    .map(|tagged| BundleTransport { group, tagged })
+            })
+            .collect()
+    }
+
+    /// The media section whose transport is used by the media section with this `a=mid`.
+    ///
+    /// That is the tagged media section if it is bundled, otherwise the media section itself.
+    pub fn transport_section(&self, mid: &str) -> Option<&MediaSection<'a>> {
+        self.bundle_group_for_mid(mid)
+            .and_then(|group| self.tagged_section(group))
+            .or_else(|| self.media_by_mid(mid))
+    }
+
+    /// The transport parameters that apply to the media section with this `a=mid`,
+    /// with session-level values filled in.
+    pub fn transport_for_mid(&self, mid: &str) -> Option<Transport<'_, 'a>> {
+        let section = self.transport_section(mid)?;
+        let resolved = self.resolve(section);
+        Some(Transport {
+            mid: section.mid.as_deref(),
+            port: section.port,
+            connection: resolved.connection,
+            ice_ufrag: resolved.ice_ufrag,
+            ice_pwd: resolved.ice_pwd,
+            ice_options: resolved.ice_options,
+            fingerprints: resolved.fingerprints,
+            setup_role: resolved.setup_role,
+            dtls_connection: resolved.dtls_connection,
+            tls_id: section.tls_id.as_ref(),
+            candidates: &section.candidates,
+        })
+    }
+
+    /// Removes transport attributes from bundled media sections other than the tagged one.
+    ///
+    /// Candidates, `a=end-of-candidates`, ICE and DTLS parameters and `a=rtcp` only matter in the tagged
+    /// media section once BUNDLE is negotiated, dropping them everywhere else makes for a smaller description.
+    ///
+    /// Only use this on answers and subsequent offers. An initial offer has to carry these attributes
+    /// in every media section that isn't `a=bundle-only`, in case the answerer doesn't support BUNDLE,
+    /// see [RFC8843 Section 7.2](https://tools.ietf.org/html/rfc8843#section-7.2).
+    pub fn collapse_bundle_transport(&mut self) {
+        let bundled = self
+            .bundle_transports()
+            .iter()
+            .flat_map(|transport| {
+                let tagged = transport.tagged_mid();
+                transport
+                    .mids()
+                    .filter(move |mid| Some(*mid) != tagged)
+                    .map(|mid| mid.to_owned())
+            })
+            .collect::<Vec<_>>();
+
+        for media in &mut self.media {
+            if !bundled
+                .iter()
+                .any(|mid| media.mid.as_deref() == Some(mid.as_str()))
+            {
+                continue;
+            }
+            media.candidates.clear();
+            media.ice.ufrag = None;
+            media.ice.pwd = None;
+            media.ice.options = None;
+            media.fingerprints.clear();
+            media.setup_role = None;
+            media.dtls_connection = None;
+            media.tls_id = None;
+            media.rtcp = None;
+            media
+                .attributes
+                .retain(|a| !matches!(a, AttributeLine::EoC));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jsep() -> Session<'static> {
+        let fixture = std::fs::read_to_string(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("fixtures/sdp_transform/jsep.sdp"),
+        )
+        .unwrap();
+        Session::read_str(&fixture).into_owned()
+    }
+
+    #[test]
+    fn resolves_tagged_transport() {
+        let session = jsep();
+        let transports = session.bundle_transports();
+        assert_eq!(transports.len(), 1);
+        assert_eq!(transports[0].tagged_mid(), Some("a1"));
+        assert_eq!(transports[0].mids().collect::<Vec<_>>(), ["a1", "v1"]);
+
+        let video = session.transport_for_mid("v1").unwrap();
+        assert_eq!(video.mid, Some("a1"));
+        assert_eq!(video.port, 56500);
+        assert_eq!(video.ice_ufrag.map(AsRef::as_ref), Some("ETEn1v9DoTMB9J4r"));
+        assert_eq!(video.candidates.len(), 2);
+    }
+
+    #[test]
+    fn skips_bundle_only_when_tagging() {
+        let session = Session::read_str(
+            "v=0
+a=group:BUNDLE 0 1 2
+m=audio 0 UDP/TLS/RTP/SAVPF 111
+a=mid:0
+a=bundle-only
+m=video 9 UDP/TLS/RTP/SAVPF 96
+a=mid:1
+a=ice-ufrag:vide
+m=video 9 UDP/TLS/RTP/SAVPF 96
+a=mid:2
+a=ice-ufrag:othr
+m=application 9 UDP/DTLS/SCTP webrtc-datachannel
+a=mid:3
+a=ice-ufrag:data",
+        );
+        assert_eq!(
+            session.transport_section("0").unwrap().mid.as_deref(),
+            Some("1")
+        );
+        assert_eq!(
+            session
+                .transport_for_mid("2")
+                .unwrap()
+                .ice_ufrag
+                .map(AsRef::as_ref),
+            Some("vide")
+        );
+        assert_eq!(
+            session
+                .transport_for_mid("3")
+                .unwrap()
+                .ice_ufrag
+                .map(AsRef::as_ref),
+            Some("data")
+        );
+        assert!(session.transport_for_mid("4").is_none());
+    }
+
+    #[test]
+    fn collapses_transport_attributes() {
+        let mut session = jsep();
+        session.collapse_bundle_transport();
+
+        let audio = &session.media[0];
+        assert_eq!(audio.candidates.len(), 2);
+        assert!(audio.ice.ufrag.is_some());
+        assert!(audio.attributes.contains(&AttributeLine::EoC));
+
+        let video = &session.media[1];
+        assert!(video.candidates.is_empty());
+        assert!(video.ice.ufrag.is_none());
+        assert!(video.fingerprints.is_empty());
+        assert!(video.rtcp.is_none());
+        assert!(!video.attributes.contains(&AttributeLine::EoC));
+
+        let resolved = session.transport_for_mid("v1").unwrap();
+        assert_eq!(
+            resolved.ice_ufrag.map(AsRef::as_ref),
+            Some("ETEn1v9DoTMB9J4r")
+        );
+    }
+}
@@ -0,0 +1,406 @@
+//! Change detection between two descriptions
+//!
+//! Compares an old and a new description of the same session media section by media section,
+//! matched by `a=mid`, to find ICE restarts, DTLS changes and renegotiated media.
+
+use crate::{
+    attributes::{
+        dtls::{ConnectionMode, SetupRole, TlsId},
+        rtpmap::RtpMap,
+        Direction, Fingerprint, Fmtp,
+    },
+    media_section::MediaSection,
+    Session,
+};
+
+/// A value that differs between the old and the new description
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Change<T> {
+    pub old: T,
+    pub new: T,
+}
+
+fn change<T: PartialEq>(old: T, new: T) -> Option<Change<T>> {
+    if old == new {
+        None
+    } else {
+        Some(Change { old, new })
+    }
+}
+
+/// Changes of the transport a media section uses, with BUNDLE and session-level values applied
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct TransportChanges<'s, 'a> {
+    /// `a=ice-ufrag` or `a=ice-pwd` changed
+    pub ice_restart: bool,
+    pub fingerprints: Option<Change<&'s [Fingerprint<'a>]>>,
+    pub setup_role: Option<Change<Option<&'s SetupRole>>>,
+    pub tls_id: Option<Change<Option<&'s TlsId<'a>>>>,
+}
+
+impl TransportChanges<'_, '_> {
+    /// Whether the certificate, the roles or the `a=tls-id` changed.
+    ///
+    /// See [`Session::requires_new_dtls_association`] for whether that means a new handshake.
+    pub fn dtls_changed(&self) -> bool {
+        self.fingerprints.is_some() || self.setup_role.is_some() || self.tls_id.is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.ice_restart && !self.dtls_changed()
+    }
+}
+
+/// A codec as `a=rtpmap` and its `a=fmtp`
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Codec<'s, 'a> {
+    pub rtp_map: &'s RtpMap<'a>,
+    pub fmtp: Option<&'s Fmtp<'a>>,
+}
+
+fn codecs<'s, 'a>(media: &'s MediaSection<'a>) -> Vec<Codec<'s, 'a>> {
+    media
+        .rtp_map
+        .iter()
+        .map(|rtp_map| Codec {
+            rtp_map,
+            fmtp: media.fmtp.iter().find(|f| f.payload == rtp_map.payload),
+        })
+        .collect()
+}
+
+/// Changes of what is sent over a media section
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct MediaChanges<'s, 'a> {
+    /// port 0 means rejected
+    pub port: Option<Change<u32>>,
+    pub direction: Option<Change<Direction>>,
+    pub added_codecs: Vec<Codec<'s, 'a>>,
+    pub removed_codecs: Vec<Codec<'s, 'a>>,
+}
+
+impl MediaChanges<'_, '_> {
+    pub fn codecs_changed(&self) -> bool {
+        !self.added_codecs.is_empty() || !self.removed_codecs.is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.port.is_none() && self.direction.is_none() && !self.codecs_changed()
+    }
+}
+
+/// Everything that changed for one `a=mid`
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct MidChanges<'s, 'a> {
+    pub mid: &'s str,
+    pub transport: TransportChanges<'s, 'a>,
+    pub media: MediaChanges<'s, 'a>,
+}
+
+impl MidChanges<'_, '_> {
+    pub fn is_empty(&self) -> bool {
+        self.transport.is_empty() && self.media.is_empty()
+    }
+}
+
+/// The difference between two descriptions of a session
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct SessionChanges<'s, 'a> {
+    /// mids only in the new description
+    pub added: Vec<&'s str>,
+    /// mids only in the old description
+    pub removed: Vec<&'s str>,
+    /// mids in both descriptions that changed
+    pub changed: Vec<MidChanges<'s, 'a>>,
+}
+
+impl<'s, 'a> SessionChanges<'s, 'a> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Whether any media section restarts ICE.
+    pub fn ice_restart(&self) -> bool {
+        self.changed.iter().any(|c| c.transport.ice_restart)
+    }
+
+    pub fn for_mid(&self, mid: &str) -> Option<&MidChanges<'s, 'a>> {
+        self.changed.iter().find(|c| c.mid == mid)
+    }
+}
+
+fn mids<'s>(session: &'s Session) -> impl Iterator<Item = &'s str> {
+    session.media.iter().filter_map(|m| m.mid.as_deref())
+}
+
+impl<'a> Session<'a> {
+    /// Compares `self` with a `new` description of the same session.
+    ///
+    /// Media sections without `a=mid` are not compared.
+    pub fn changes<'s>(&'s self, new: &'s Session<'a>) -> SessionChanges<'s, 'a> {
+        let added = mids(new)
+            .filter(|mid| self.media_by_mid(mid).is_none())
+            .collect();
+        let removed = mids(self)
+            .filter(|mid| new.media_by_mid(mid).is_none())
+            .collect();
+
+        let changed = mids(self)
+            .filter_map(|mid| {
+                let old_media = self.media_by_mid(mid)?;
+                let new_media = new.media_by_mid(mid)?;
+                let old_transport = self.transport_for_mid(mid)?;
+                let new_transport = new.transport_for_mid(mid)?;
+
+                let transport = TransportChanges {
+                    ice_restart: old_transport.ice_ufrag != new_transport.ice_ufrag
+                        || old_transport.ice_pwd != new_transport.ice_pwd,
+                    fingerprints: change(old_transport.fingerprints, new_transport.fingerprints),
+                    setup_role: change(old_transport.setup_role, new_transport.setup_role),
+                    tls_id: change(old_transport.tls_id, new_transport.tls_id),
+                };
+
+                let old_codecs = codecs(old_media);
+                let new_codecs = codecs(new_media);
+                let media = MediaChanges {
+                    port: change(old_media.port, new_media.port),
+                    direction: change(
+                        old_media.effective_direction(self),
+                        new_media.effective_direction(new),
+                    ),
+                    added_codecs: new_codecs
+                        .iter()
+                        .filter(|codec| !old_codecs.contains(codec))
+                        .copied()
+                        .collect(),
+                    removed_codecs: old_codecs
+                        .iter()
+                        .filter(|codec| !new_codecs.contains(codec))
+                        .copied()
+                        .collect(),
+                };
+
+                Some(MidChanges {
+                    mid,
+                    transport,
+                    media,
+                })
+                .filter(|changes| !changes.is_empty())
+            })
+            .collect();
+
+        SessionChanges {
+            added,
+            removed,
+            changed,
+        }
+    }
+
+    /// Whether the media section with this `a=mid` needs a new DTLS association
+    /// when `self` is replaced by the `new` description.
+    ///
+    /// With `a=tls-id` on both sides the association is kept as long as the id stays the same
+    /// ([RFC8842](https://tools.ietf.org/html/rfc8842#section-5)).
+    /// Otherwise `a=connection` decides ([RFC4145](https://tools.ietf.org/html/rfc4145#section-5)),
+    /// and without that an ICE restart or a new fingerprint requires a new association.
+    /// Rejected media sections and `a=setup:holdconn` don't have an association.
+    pub fn requires_new_dtls_association(&self, new: &Session, mid: &str) -> bool {
+        let new_transport = match new.transport_for_mid(mid) {
+            Some(transport) => transport,
+            None => return false,
+        };
+        if new_transport.port == 0 || new_transport.setup_role == Some(&SetupRole::HoldConn) {
+            return false;
+        }
+        let old_transport = match self.transport_for_mid(mid) {
+            Some(transport)
+                if transport.port != 0 && transport.setup_role != Some(&SetupRole::HoldConn) =>
+            {
+                transport
+            }
+            _ => return true,
+        };
+
+        if let (Some(old), Some(new)) = (old_transport.tls_id, new_transport.tls_id) {
+            return old != new;
+        }
+        match new_transport.dtls_connection {
+            Some(ConnectionMode::New) => true,
+            Some(ConnectionMode::Existing) => false,
+            None => {
+                old_transport.ice_ufrag != new_transport.ice_ufrag
+                    || old_transport.ice_pwd != new_transport.ice_pwd
+                    || old_transport.fingerprints != new_transport.fingerprints
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = "v=0
+a=ice-ufrag:abcd
+a=ice-pwd:abcdabcdabcdabcdabcdabcd
+a=fingerprint:sha-256 19:E2:1C:3B:4B:9F:81:E6:B8:5C:F4:A5:A8:D8:73:04:BB:05:2F:70:9F:04:A9:0E:05:E9:26:33:E8:70:88:A2
+a=group:BUNDLE 0 1
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=mid:0
+a=setup:actpass
+a=sendrecv
+a=rtpmap:111 opus/48000/2
+a=fmtp:111 minptime=10
+m=video 9 UDP/TLS/RTP/SAVPF 96
+a=mid:1
+a=setup:actpass
+a=rtpmap:96 VP8/90000
+m=application 9 UDP/DTLS/SCTP webrtc-datachannel
+a=mid:2
+";
+
+    #[test]
+    fn no_changes() {
+        let old = Session::read_str(OLD);
+        let new = Session::read_str(OLD);
+        assert!(old.changes(&new).is_empty());
+    }
+
+    #[test]
+    fn detects_ice_restart_and_dtls_changes() {
+        let old = Session::read_str(OLD);
+        let restarted = OLD
+            .replace("ice-ufrag:abcd", "ice-ufrag:efgh")
+            .replace("a=setup:actpass\na=sendrecv", "a=setup:active\na=sendrecv");
+        let new = Session::read_str(&restarted);
+        let changes = old.changes(&new);
+
+        assert!(changes.ice_restart());
+        let audio = changes.for_mid("0").unwrap();
+        assert!(audio.transport.ice_restart);
+        assert_eq!(
+            audio.transport.setup_role,
+            Some(Change {
+                old: Some(&SetupRole::ActPass),
+                new: Some(&SetupRole::Active)
+            })
+        );
+        assert!(audio.transport.fingerprints.is_none());
+        assert!(audio.media.is_empty());
+
+        // video is bundled on audio
+        let video = changes.for_mid("1").unwrap();
+        assert!(video.transport.ice_restart);
+        assert!(video.transport.dtls_changed());
+        assert!(changes.for_mid("2").unwrap().transport.ice_restart);
+    }
+
+    #[test]
+    fn detects_media_changes() {
+        let old = Session::read_str(OLD);
+        let renegotiated = OLD
+            .replace("a=sendrecv", "a=recvonly")
+            .replace("minptime=10", "minptime=20")
+            .replace(
+                "m=video 9 UDP/TLS/RTP/SAVPF 96\na=mid:1",
+                "m=video 0 UDP/TLS/RTP/SAVPF 96\na=mid:1",
+            )
+            .replace("a=mid:2", "a=mid:3");
+        let new = Session::read_str(&renegotiated);
+        let changes = old.changes(&new);
+
+        assert_eq!(changes.added, ["3"]);
+        assert_eq!(changes.removed, ["2"]);
+        assert!(!changes.ice_restart());
+
+        let audio = changes.for_mid("0").unwrap();
+        assert_eq!(
+            audio.media.direction,
+            Some(Change {
+                old: Direction::SendRecv,
+                new: Direction::RecvOnly
+            })
+        );
+        assert!(audio.media.codecs_changed());
+        assert_eq!(
+            audio.media.added_codecs[0].fmtp.unwrap().config,
+            "minptime=20"
+        );
+        assert_eq!(
+            audio.media.removed_codecs[0].fmtp.unwrap().config,
+            "minptime=10"
+        );
+
+        let video = changes.for_mid("1").unwrap();
+        assert_eq!(video.media.port, Some(Change { old: 9, new: 0 }));
+        assert_eq!(
+            video.media.direction,
+            Some(Change {
+                old: Direction::SendRecv,
+                new: Direction::Inactive
+            })
+        );
+    }
+    #[test]
+    fn dtls_association() {
+        let old = Session::read_str(OLD);
+        assert!(!old.requires_new_dtls_association(&Session::read_str(OLD), "0"));
+        // mid 2 is gone
+        let removed = OLD.replace("a=mid:2", "a=mid:3");
+        assert!(!old.requires_new_dtls_association(&Session::read_str(&removed), "2"));
+
+        // without tls-id an ICE restart needs a new association unless a=connection says otherwise
+        let restarted = OLD.replace("ice-ufrag:abcd", "ice-ufrag:efgh");
+        assert!(old.requires_new_dtls_association(&Session::read_str(&restarted), "0"));
+        let existing = restarted.replace("a=mid:0", "a=mid:0\na=connection:existing");
+        assert!(!old.requires_new_dtls_association(&Session::read_str(&existing), "0"));
+        let renewed = OLD.replace("a=mid:0", "a=mid:0\na=connection:new");
+        assert!(old.requires_new_dtls_association(&Session::read_str(&renewed), "0"));
+        // a=connection at session level applies to every media section
+        let existing = restarted.replace("v=0\n", "v=0\na=connection:existing\n");
+        let existing = Session::read_str(&existing);
+        assert_eq!(existing.dtls_connection, Some(ConnectionMode::Existing));
+        assert!(!old.requires_new_dtls_association(&existing, "0"));
+        assert!(!old.requires_new_dtls_association(&existing, "2"));
+
+        // with tls-id only the id counts
+        let with_id = OLD.replace("a=mid:0", "a=mid:0\na=tls-id:abc3de65cddef001be82");
+        let old = Session::read_str(&with_id);
+        let restarted = with_id.replace("ice-ufrag:abcd", "ice-ufrag:efgh");
+        let restarted = Session::read_str(&restarted);
+        assert!(!old.requires_new_dtls_association(&restarted, "0"));
+        // video is bundled on audio
+        assert!(!old.requires_new_dtls_association(&restarted, "1"));
+        let new_id = with_id.replace("abc3de65cddef001be82", "def3de65cddef001be83");
+        let new_id = Session::read_str(&new_id);
+        assert!(old.requires_new_dtls_association(&new_id, "1"));
+        assert!(old
+            .changes(&new_id)
+            .for_mid("0")
+            .unwrap()
+            .transport
+            .dtls_changed());
+
+        // rejected and holdconn media sections have no association
+        let old = Session::read_str(OLD);
+        let rejected = OLD
+            .replace("m=audio 9", "m=audio 0")
+            .replace("a=group:BUNDLE 0 1\n", "");
+        let rejected = Session::read_str(&rejected);
+        assert!(!old.requires_new_dtls_association(&rejected, "0"));
+        assert!(rejected.requires_new_dtls_association(&old, "0"));
+        let held = OLD.replace(
+            "a=setup:actpass\na=sendrecv",
+            "a=setup:holdconn\na=sendrecv",
+        );
+        let held = Session::read_str(&held);
+        assert!(!old.requires_new_dtls_association(&held, "0"));
+        assert!(held.requires_new_dtls_association(&old, "0"));
+    }
+}
@@ -0,0 +1,332 @@
+//! Session-level attributes that apply to every media section
+//!
+//! `a=fingerprint`, `a=setup`, `a=connection`, `a=ice-ufrag`, `a=ice-pwd`, `a=ice-options`, direction, `c=` and
+//! `a=extmap` may be given once at session level instead of in every media section.
+//! A value in a media section always takes precedence.
+
+use std::borrow::Cow;
+
+use crate::{
+    attributes::{
+        dtls::{ConnectionMode, SetupRole},
+        extmap::Extmap,
+        AttributeLine, Direction, Fingerprint, IceOptions,
+    },
+    lines::connection::Connection,
+    media_section::MediaSection,
+    Session,
+};
+
+/// The inheritable attributes of one media section with session-level values filled in.
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct ResolvedMedia<'s, 'a> {
+    pub connection: Option<&'s Connection>,
+    /// media-level fingerprints, or the session-level ones if there are none
+    pub fingerprints: &'s [Fingerprint<'a>],
+    pub setup_role: Option<&'s SetupRole>,
+    pub dtls_connection: Option<&'s ConnectionMode>,
+    pub ice_ufrag: Option<&'s Cow<'a, str>>,
+    pub ice_pwd: Option<&'s Cow<'a, str>>,
+    pub ice_options: Option<&'s IceOptions<'a>>,
+    pub direction: Direction,
+    /// media-level extmaps followed by session-level ones with an id that isn't taken
+    pub extmap: Vec<&'s Extmap<'a>>,
+}
+
+fn is_inheritable(attr: &AttributeLine) -> bool {
+    matches!(attr, AttributeLine::Direction(_) | AttributeLine::Extmap(_))
+}
+
+/// the one value all media sections agree on
+fn common<'m, 'a: 'm, T: PartialEq + 'm>(
+    media: &'m [MediaSection<'a>],
+    f: impl Fn(&'m MediaSection<'a>) -> Option<&'m T>,
+) -> Option<&'m T> {
+    let first = f(media.first()?)?;
+    if media.iter().all(|m| f(m) == Some(first)) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+impl<'a> Session<'a> {
+    /// Resolves the attributes that apply to `media`, which should be one of `self.media`.
+    pub fn resolve<'s>(&'s self, media: &'s MediaSection<'a>) -> ResolvedMedia<'s, 'a> {
+        let mut extmap = media.extmap.iter().collect::<Vec<_>>();
+        extmap.extend(
+            self.attributes
+                .iter()
+                .filter_map(|a| a.as_extmap())
+                .filter(|ext| !media.extmap.iter().any(|e| e.value == ext.value)),
+        );
+
+        ResolvedMedia {
+            connection: media.connection.as_ref().or(self.connection.as_ref()),
+            fingerprints: if media.fingerprints.is_empty() {
+                &self.fingerprints
+            } else {
+                &media.fingerprints
+            },
+            setup_role: media.setup_role.as_ref().or(self.setup_role.as_ref()),
+            dtls_connection: media
+                .dtls_connection
+                .as_ref()
+                .or(self.dtls_connection.as_ref()),
+            ice_ufrag: media.ice.ufrag.as_ref().or(self.ice.ufrag.as_ref()),
+            ice_pwd: media.ice.pwd.as_ref().or(self.ice.pwd.as_ref()),
+            ice_options: media.ice.options.as_ref().or(self.ice.options.as_ref()),
+            direction: media.effective_direction(self),
+            extmap,
+        }
+    }
+
+    /// Resolves the attributes of all media sections.
+    pub fn resolve_all(&self) -> Vec<ResolvedMedia<'_, 'a>> {
+        self.media.iter().map(|m| self.resolve(m)).collect()
+    }
+
+    /// Moves session-level values into every media section that doesn't have its own.
+    ///
+    /// Without media sections the session-level values are kept.
+    pub fn push_down_attributes(&mut self) {
+        if self.media.is_empty() {
+            return;
+        }
+        let connection = self.connection.take();
+        let fingerprints = std::mem::take(&mut self.fingerprints);
+        let setup_role = self.setup_role.take();
+        let dtls_connection = self.dtls_connection.take();
+        let ufrag = self.ice.ufrag.take();
+        let pwd = self.ice.pwd.take();
+        let options = self.ice.options.take();
+        let direction = self
+            .attributes
+            .iter()
+            .find_map(|a| a.as_direction())
+            .copied();
+        let extmap = self
+            .attributes
+            .iter()
+            .filter_map(|a| a.as_extmap())
+            .cloned()
+            .collect::<Vec<_>>();
+        self.attributes.retain(|a| !is_inheritable(a));
+
+        for media in &mut self.media {
+            if media.connection.is_none() {
+                media.connection = connection.clone();
+            }
+            if media.fingerprints.is_empty() {
+                media.fingerprints = fingerprints.clone();
+            }
+            if media.setup_role.is_none() {
+                media.setup_role = setup_role.clone();
+            }
+            if media.dtls_connection.is_none() {
+                media.dtls_connection = dtls_connection;
+            }
+            if media.ice.ufrag.is_none() {
+                media.ice.ufrag = ufrag.clone();
+            }
+            if media.ice.pwd.is_none() {
+                media.ice.pwd = pwd.clone();
+            }
+            if media.ice.options.is_none() {
+                media.ice.options = options.clone();
+            }
+            if media.direction.is_none() {
+                media.direction = direction;
+            }
+            for ext in &extmap {
+                if !media.extmap.iter().any(|e| e.value == ext.value) {
+                    media.extmap.push(ext.clone());
+                }
+            }
+        }
+    }
+
+    /// Moves values that are the same in every media section up to session level.
+    ///
+    /// This is the opposite of [`Session::push_down_attributes`],
+    /// session-level values that already exist are pushed down first.
+    pub fn hoist_attributes(&mut self) {
+        if self.media.is_empty() {
+            return;
+        }
+        self.push_down_attributes();
+        let media = &self.media;
+
+        let connection = common(media, |m| m.connection.as_ref()).cloned();
+        let fingerprints =
+            common(media, |m| Some(&m.fingerprints).filter(|f| !f.is_empty())).cloned();
+        let setup_role = common(media, |m| m.setup_role.as_ref()).cloned();
+        let dtls_connection = common(media, |m| m.dtls_connection.as_ref()).copied();
+        let ufrag = common(media, |m| m.ice.ufrag.as_ref()).cloned();
+        let pwd = common(media, |m| m.ice.pwd.as_ref()).cloned();
+        let options = common(media, |m| m.ice.options.as_ref()).cloned();
+        let direction = common(media, |m| m.direction.as_ref()).copied();
+        let extmap = media[0]
+            .extmap
+            .iter()
+            .filter(|ext| media.iter().all(|m| m.extmap.contains(ext)))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for media in &mut self.media {
+            if connection.is_some() {
+                media.connection = None;
+            }
+            if fingerprints.is_some() {
+                media.fingerprints.clear();
+            }
+            if setup_role.is_some() {
+                media.setup_role = None;
+            }
+            if dtls_connection.is_some() {
+                media.dtls_connection = None;
+            }
+            if ufrag.is_some() {
+                media.ice.ufrag = None;
+            }
+            if pwd.is_some() {
+                media.ice.pwd = None;
+            }
+            if options.is_some() {
+                media.ice.options = None;
+            }
+            if direction.is_some() {
+                media.direction = None;
+            }
+            media.extmap.retain(|ext| !extmap.contains(ext));
+        }
+
+        self.connection = connection;
+        self.fingerprints = fingerprints.unwrap_or_default();
+        self.setup_role = setup_role;
+        self.dtls_connection = dtls_connection;
+        self.ice.ufrag = ufrag;
+        self.ice.pwd = pwd;
+        self.ice.options = options;
+        self.attributes.extend(
+            direction
+                .map(AttributeLine::Direction)
+                .into_iter()
+                .chain(extmap.into_iter().map(AttributeLine::Extmap)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SDP: &str = "v=0
+o=- 1 1 IN IP4 127.0.0.1
+s=-
+t=0 0
+c=IN IP4 203.0.113.1
+a=ice-ufrag:sess
+a=ice-pwd:sessionsessionsessionsess
+a=fingerprint:sha-256 19:E2:1C:3B:4B:9F:81:E6:B8:5C:F4:A5:A8:D8:73:04:BB:05:2F:70:9F:04:A9:0E:05:E9:26:33:E8:70:88:A2
+a=setup:actpass
+a=recvonly
+a=extmap:1 urn:ietf:params:rtp-hdrext:sdes:mid
+a=extmap:2 urn:ietf:params:rtp-hdrext:toffset
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=ice-ufrag:audi
+a=mid:0
+a=extmap:2 urn:ietf:params:rtp-hdrext:ssrc-audio-level
+m=video 9 UDP/TLS/RTP/SAVPF 96
+c=IN IP4 203.0.113.2
+a=mid:1
+a=sendrecv
+";
+
+    #[test]
+    fn media_overrides_session() {
+        let session = Session::read_str(SDP);
+        let audio = session.resolve(&session.media[0]);
+        let video = session.resolve(&session.media[1]);
+
+        assert_eq!(audio.ice_ufrag.map(AsRef::as_ref), Some("audi"));
+        assert_eq!(video.ice_ufrag.map(AsRef::as_ref), Some("sess"));
+        assert_eq!(audio.ice_pwd, video.ice_pwd);
+        assert_eq!(audio.setup_role, Some(&SetupRole::ActPass));
+        assert_eq!(audio.fingerprints.len(), 1);
+
+        assert_eq!(audio.direction, Direction::RecvOnly);
+        assert_eq!(video.direction, Direction::SendRecv);
+
+        assert_eq!(audio.connection, session.connection.as_ref());
+        assert_eq!(video.connection, session.media[1].connection.as_ref());
+
+        let audio_extensions = audio
+            .extmap
+            .iter()
+            .map(|e| e.uri.as_ref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            audio_extensions,
+            [
+                "urn:ietf:params:rtp-hdrext:ssrc-audio-level",
+                "urn:ietf:params:rtp-hdrext:sdes:mid"
+            ]
+        );
+        assert_eq!(video.extmap.len(), 2);
+    }
+
+    #[test]
+    fn push_down_and_hoist() {
+        let mut session = Session::read_str(SDP);
+        session.push_down_attributes();
+        assert!(session.attributes.is_empty());
+        assert!(session.connection.is_none());
+        assert_eq!(session.media[0].ice.ufrag.as_deref(), Some("audi"));
+        assert_eq!(session.media[1].ice.ufrag.as_deref(), Some("sess"));
+        assert_eq!(session.media[0].direction, Some(Direction::RecvOnly));
+        assert_eq!(session.media[0].extmap.len(), 2);
+
+        let resolved_before = Session::read_str(SDP)
+            .resolve_all()
+            .iter()
+            .map(|r| (r.direction, r.ice_ufrag.cloned(), r.fingerprints.to_vec()))
+            .collect::<Vec<_>>();
+
+        session.hoist_attributes();
+        assert!(session.media[0].fingerprints.is_empty());
+        assert!(session.media[0].ice.pwd.is_none());
+        assert!(session.media[0].ice.ufrag.is_some());
+        assert!(session.connection.is_none());
+        assert_eq!(session.media[1].extmap.len(), 1);
+
+        let resolved_after = session
+            .resolve_all()
+            .iter()
+            .map(|r| (r.direction, r.ice_ufrag.cloned(), r.fingerprints.to_vec()))
+            .collect::<Vec<_>>();
+        assert_eq!(resolved_before, resolved_after);
+    }
+
+    #[test]
+    fn push_down_without_media() {
+        let sdp = "v=0
+o=- 1 1 IN IP4 127.0.0.1
+s=-
+t=0 0
+c=IN IP4 203.0.113.1
+a=ice-ufrag:sess
+a=setup:actpass
+a=recvonly
+";
+        let mut session = Session::read_str(sdp);
+        session.push_down_attributes();
+        assert!(session.connection.is_some());
+        assert_eq!(session.setup_role, Some(SetupRole::ActPass));
+        assert_eq!(session.ice.ufrag.as_deref(), Some("sess"));
+        assert_eq!(
+            session.attributes,
+            [AttributeLine::Direction(Direction::RecvOnly)]
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! Conversion between Plan B and Unified Plan
+//!
+//! Plan B puts all tracks of one media type into a single media section and tells them apart by
+//! `a=ssrc:<id> msid:<stream> <track>`, Unified Plan uses one media section per track
+//! identified by `a=msid`.
+
+use std::borrow::Cow;
+
+use crate::{
+    attributes::{msid::Msid, Direction, Ssrc, SsrcAttribute},
+    media_section::MediaSection,
+    Session,
+};
+
+/// The SSRCs of one track, primary SSRCs together with their RTX and FEC SSRCs
+struct Track {
+    stream_id: Option<String>,
+    track_id: String,
+    ssrcs: Vec<u32>,
+}
+
+fn tracks(media: &MediaSection) -> Vec<Track> {
+    let mut tracks: Vec<Track> = Vec::new();
+    for stream in media.ssrc_streams() {
+        let track_id = match stream.track_id.or(stream.label) {
+            Some(track_id) => track_id,
+            None => continue,
+        };
+        let ssrcs = std::iter::once(stream.ssrc)
+            .chain(stream.rtx)
+            .chain(stream.fec);
+        if let Some(track) = tracks.iter_mut().find(|t| t.track_id == track_id) {
+            track.ssrcs.extend(ssrcs);
+        } else {
+            tracks.push(Track {
+                stream_id: stream.stream_id.or(stream.mslabel).map(ToOwned::to_owned),
+                track_id: track_id.to_owned(),
+                ssrcs: ssrcs.collect(),
+            });
+        }
+    }
+    tracks
+}
+
+/// the smallest number that isn't used as a mid yet
+fn next_mid(mids: &mut Vec<String>) -> String {
+    let mid = (0..)
+        .map(|n: usize| n.to_string())
+        .find(|mid| !mids.contains(mid))
+        .expect("there are always unused mids");
+    mids.push(mid.clone());
+    mid
+}
+
+impl<'a> Session<'a> {
+    /// Whether any media section carries more than one track.
+    pub fn is_plan_b(&self) -> bool {
+        self.media.iter().any(|media| tracks(media).len() > 1)
+    }
+
+    /// Splits every media section carrying several tracks into one media section per track.
+    ///
+    /// The first track keeps the original media section and its `a=mid`, the others get new mids
+    /// and are added to the groups of the original right after it.
+    /// Every media section with a track gets an `a=msid` for it.
+    pub fn to_unified_plan(&mut self) {
+        let mut mids = self
+            .media
+            .iter()
+            .filter_map(|m| m.mid.as_deref().map(ToOwned::to_owned))
+            .collect::<Vec<_>>();
+        let mut media = Vec::with_capacity(self.media.len());
+
+        for section in std::mem::take(&mut self.media) {
+            let tracks = tracks(&section);
+            if tracks.is_empty() {
+                media.push(section);
+                continue;
+            }
+
+            let mut new_mids = Vec::new();
+            for (i, track) in tracks.iter().enumerate() {
+                let mut split = section.clone();
+                split.ssrc.retain(|ssrc| track.ssrcs.contains(&ssrc.id));
+                split
+                    .ssrc_groups
+                    .retain(|group| group.ids.iter().all(|id| track.ssrcs.contains(id)));
+                split
+                    .msids
+                    .retain(|msid| msid.track_id.as_deref() == Some(track.track_id.as_str()));
+                if split.msids.is_empty() {
+                    split.msids.push(Msid {
+                        stream_id: track.stream_id.clone().map(Cow::from),
+                        track_id: Some(track.track_id.clone().into()),
+                    });
+                }
+                if i > 0 {
+                    let mid = next_mid(&mut mids);
+                    new_mids.push(Cow::from(mid.clone()));
+                    split.mid = Some(mid.into());
+                }
+                media.push(split);
+            }
+
+            if let Some(mid) = section.mid {
+                for group in self.groups.iter_mut() {
+                    if let Some(pos) = group.mids.iter().position(|m| *m == mid) {
+                        group
+                            .mids
+                            .splice(pos + 1..pos + 1, new_mids.iter().cloned());
+                    }
+                }
+            }
+        }
+        self.media = media;
+    }
+
+    /// Merges all media sections of the same type into the first one, the opposite of [`Session::to_unified_plan`].
+    ///
+    /// Only media sections that share a `BUNDLE` transport are merged, a Plan B media section
+    /// can't use several transports.
+    /// The SSRCs of merged media sections are labeled with their `a=msid` if they aren't already,
+    /// rejected media sections are left alone.
+    pub fn to_plan_b(&mut self) {
+        // index of the bundle transport of every media section
+        let transports = {
+            let bundles = self.bundle_transports();
+            self.media
+                .iter()
+                .map(|m| {
+                    let mid = m.mid.as_deref()?;
+                    bundles.iter().position(|b| b.mids().any(|b| b == mid))
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut media: Vec<MediaSection<'a>> = Vec::with_capacity(self.media.len());
+        let mut media_transports = Vec::with_capacity(self.media.len());
+        let mut removed_mids = Vec::new();
+
+        for (mut section, transport) in std::mem::take(&mut self.media).into_iter().zip(transports)
+        {
+            let target = media.iter().zip(&media_transports).position(|(m, t)| {
+                transport.is_some()
+                    && *t == transport
+                    && section.port != 0
+                    && m.port != 0
+                    && m.r#type == section.r#type
+            });
+            let target = match target {
+                Some(target) => &mut media[target],
+                None => {
+                    media.push(section);
+                    media_transports.push(transport);
+                    continue;
+                }
+            };
+
+            label_ssrcs(target);
+            label_ssrcs(&mut section);
+
+            if target.direction.is_some() || section.direction.is_some() {
+                let a = target.direction.unwrap_or(Direction::SendRecv);
+                let b = section.direction.unwrap_or(Direction::SendRecv);
+                target.direction = Some(Direction::from_flags(
+                    a.sends() || b.sends(),
+                    a.receives() || b.receives(),
+                ));
+            }
+            target.ssrc_groups.append(&mut section.ssrc_groups);
+            target.ssrc.append(&mut section.ssrc);
+            removed_mids.extend(section.mid);
+        }
+
+        for group in self.groups.iter_mut() {
+            group.mids.retain(|mid| !removed_mids.contains(mid));
+        }
+        self.media = media;
+    }
+}
+
+/// Label every SSRC of `section` that has no `msid` yet with the section's first `a=msid`,
+/// so it can still be told apart once other tracks' SSRCs are appended.
+fn label_ssrcs(section: &mut MediaSection) {
+    let msid = match section.msids.first() {
+        Some(msid) => msid.clone(),
+        None => return,
+    };
+    let mut unlabeled: Vec<u32> = Vec::new();
+    for ssrc in &section.ssrc {
+        let labeled = section
+            .ssrc
+            .iter()
+            .any(|other| other.id == ssrc.id && matches!(other.attribute, SsrcAttribute::Msid(_)));
+        if !labeled && !unlabeled.contains(&ssrc.id) {
+            unlabeled.push(ssrc.id);
+        }
+    }
+    for id in unlabeled {
+        section.ssrc.push(Ssrc {
+            id,
+            attribute: SsrcAttribute::Msid(msid.clone()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        std::fs::read_to_string(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("fixtures/order_preserving")
+                .join(name),
+        )
+        .unwrap()
+    }
+
+    const SECOND_TRACK: &str = "a=ssrc-group:FID 2231627014 632943048
+a=ssrc:2231627014 cname:mNlgMpULjnGBD+Bt
+a=ssrc:2231627014 msid:wYMHgchcIvfAdYxfDSJJhiVLyaTKph1xiqr8 daed9400-d0dd-4db3-b949-422499e96e2d
+a=ssrc:632943048 cname:mNlgMpULjnGBD+Bt
+a=ssrc:632943048 msid:wYMHgchcIvfAdYxfDSJJhiVLyaTKph1xiqr8 daed9400-d0dd-4db3-b949-422499e96e2d
+";
+
+    #[test]
+    #[cfg(feature = "udisplay")]
+    fn chrome_fixtures_are_unified_plan() {
+        for name in [
+            "chrome.sdp",
+            "chrome-two-noise.sdp",
+            "chrome-with-candidates.sdp",
+        ] {
+            let fixture = fixture(name);
+            let mut session = Session::read_str(&fixture);
+            assert!(!session.is_plan_b());
+
+            session.to_unified_plan();
+            assert_eq!(session.to_string(), fixture);
+            session.to_plan_b();
+            assert_eq!(session.to_string(), fixture);
+        }
+    }
+
+    #[test]
+    fn splits_and_merges_tracks() {
+        let plan_b = fixture("chrome-with-candidates.sdp") + SECOND_TRACK;
+        let mut session = Session::read_str(&plan_b);
+        assert!(session.is_plan_b());
+
+        session.to_unified_plan();
+        assert!(!session.is_plan_b());
+        assert_eq!(session.media.len(), 2);
+        assert_eq!(session.groups[0].mids, ["1", "0"]);
+
+        let first = &session.media[0];
+        assert_eq!(first.mid.as_deref(), Some("1"));
+        assert_eq!(
+            first.track_id(),
+            Some("86d315d4-436a-4d83-bda9-01fdf303dccc")
+        );
+        assert_eq!(first.ssrc.len(), 8);
+        assert_eq!(first.ssrc_groups[0].ids, [306837937, 4049932520]);
+
+        let second = &session.media[1];
+        assert_eq!(second.mid.as_deref(), Some("0"));
+        assert_eq!(
+            second.track_id(),
+            Some("daed9400-d0dd-4db3-b949-422499e96e2d")
+        );
+        assert_eq!(
+            second.stream_ids().collect::<Vec<_>>(),
+            ["wYMHgchcIvfAdYxfDSJJhiVLyaTKph1xiqr8"]
+        );
+        assert_eq!(second.ssrc.len(), 4);
+        assert_eq!(second.ssrc_groups[0].ids, [2231627014, 632943048]);
+
+        session.to_plan_b();
+        assert_eq!(session, Session::read_str(&plan_b));
+    }
+
+    #[test]
+    fn labels_ssrcs_when_merging() {
+        let mut session = Session::read_str(
+            "v=0
+a=group:BUNDLE 0 1
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=mid:0
+a=recvonly
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=mid:1
+a=sendonly
+a=msid:stream track
+a=ssrc:1 cname:foo",
+        );
+        session.to_plan_b();
+        assert_eq!(session.media.len(), 1);
+        assert_eq!(session.groups[0].mids, ["0"]);
+        assert_eq!(session.media[0].direction, Some(Direction::SendRecv));
+        let streams = session.media[0].ssrc_streams();
+        assert_eq!(streams[0].stream_id, Some("stream"));
+        assert_eq!(streams[0].track_id, Some("track"));
+    }
+
+    #[test]
+    fn labels_every_merged_ssrc() {
+        let mut session = Session::read_str(
+            "v=0
+a=group:BUNDLE 0 1
+m=video 9 UDP/TLS/RTP/SAVPF 96
+a=mid:0
+a=msid:stream first
+a=ssrc-group:FID 1 2
+a=ssrc:1 cname:foo
+a=ssrc:2 cname:foo
+m=video 9 UDP/TLS/RTP/SAVPF 96
+a=mid:1
+a=msid:stream second
+a=ssrc:3 cname:foo",
+        );
+        session.to_plan_b();
+        assert_eq!(session.media.len(), 1);
+        let tracks = session.media[0]
+            .ssrc
+            .iter()
+            .filter_map(|ssrc| match &ssrc.attribute {
+                SsrcAttribute::Msid(msid) => Some((ssrc.id, msid.track_id.as_deref())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tracks,
+            [(1, Some("first")), (2, Some("first")), (3, Some("second"))]
+        );
+    }
+
+    #[test]
+    fn merges_only_within_bundle_transports() {
+        let sdp = "v=0
+a=group:BUNDLE 0 1
+a=group:BUNDLE 2 3
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=mid:0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=mid:1
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=mid:2
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=mid:3
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=mid:4
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=mid:5
+";
+        let mut session = Session::read_str(sdp);
+        session.to_plan_b();
+        let mids = session
+            .media
+            .iter()
+            .filter_map(|m| m.mid.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(mids, ["0", "2", "4", "5"]);
+        assert_eq!(session.groups[0].mids, ["0"]);
+        assert_eq!(session.groups[1].mids, ["2"]);
+    }
+}
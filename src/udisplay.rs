@@ -2,22 +2,25 @@ use ufmt::{uWrite, uwrite, uwriteln, Formatter};
 
 use crate::{
     attributes::{
-        bundle::BundleGroup,
-        candidate::{Candidate, CandidateComponent, CandidateProtocol, CandidateType},
+        candidate::{
+            Candidate, CandidateAddress, CandidateComponent, CandidateProtocol, CandidateType,
+            TcpType,
+        },
         control::Control,
         direction::Direction,
-        dtls::SetupRole,
-        extmap::Extmap,
+        dtls::{ConnectionMode, SetupRole, TlsId},
+        extmap::{Extmap, ENCRYPT_URI},
         fingerprint::Fingerprint,
         fmtp::Fmtp,
-        ice::IceParameter,
+        group::Group,
+        ice::{IceOptions, IceParameter, RemoteCandidate},
         mid::Mid,
         msid::*,
         rtcp::*,
         rtcp_option::RtcpOption,
         rtp::Rtp,
         rtpmap::*,
-        ssrc::{Ssrc, SsrcGroup, SsrcSemantic},
+        ssrc::{Ssrc, SsrcAttribute, SsrcGroup},
         AttributeLine,
     },
     lines::{
@@ -26,6 +29,8 @@ use crate::{
     },
     media_section::MediaSection,
     parsers::IpVer,
+    sdpfrag::{SdpFrag, SdpFragMedia},
+    session::SessionAttribute,
     SdpLine, Session,
 };
 impl ufmt::uDisplay for Session<'_> {
@@ -44,8 +49,13 @@ impl ufmt::uDisplay for Session<'_> {
         write_ln_option(f, &self.connection)?;
         write_ln_option(f, &self.description)?;
 
-        for x in &self.attributes {
-            uwriteln!(f, "{}", x)?;
+        // lines in the order they were read, then whatever was added since
+        let mut cursor = SessionAttributeCursor::default();
+        for kind in &self.attribute_order {
+            cursor.write_next(f, self, *kind)?;
+        }
+        for kind in SessionAttribute::DEFAULT_ORDER {
+            while cursor.write_next(f, self, kind)? {}
         }
 
         for x in &self.media {
@@ -55,6 +65,96 @@ impl ufmt::uDisplay for Session<'_> {
     }
 }
 
+/// how many lines of each kind of session-level attribute were written
+#[derive(Default)]
+struct SessionAttributeCursor {
+    groups: usize,
+    fingerprints: usize,
+    attributes: usize,
+    written: Vec<SessionAttribute>,
+}
+
+impl SessionAttributeCursor {
+    /// Writes the next line of this kind, if there is one left.
+    fn write_next<W>(
+        &mut self,
+        f: &mut Formatter<'_, W>,
+        session: &Session,
+        kind: SessionAttribute,
+    ) -> Result<bool, W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        fn write_nth<W, T: ufmt::uDisplay>(
+            f: &mut Formatter<'_, W>,
+            lines: &[T],
+            n: &mut usize,
+        ) -> Result<bool, W::Error>
+        where
+            W: uWrite + ?Sized,
+        {
+            match lines.get(*n) {
+                Some(line) => {
+                    *n += 1;
+                    uwriteln!(f, "{}", line)?;
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        }
+
+        match kind {
+            SessionAttribute::Group => return write_nth(f, &session.groups, &mut self.groups),
+            SessionAttribute::Fingerprint => {
+                return write_nth(f, &session.fingerprints, &mut self.fingerprints)
+            }
+            SessionAttribute::Other => {
+                return write_nth(f, &session.attributes, &mut self.attributes)
+            }
+            _ if self.written.contains(&kind) => return Ok(false),
+            _ => self.written.push(kind),
+        }
+        let line = match kind {
+            SessionAttribute::ExtmapAllowMixed if session.extmap_allow_mixed => {
+                Some(AttributeLine::ExtmapAllowMixed)
+            }
+            SessionAttribute::Cryptex if session.cryptex => Some(AttributeLine::Cryptex),
+            SessionAttribute::MsidSemantic => session
+                .msid_semantic
+                .clone()
+                .map(AttributeLine::MsidSemantic),
+            SessionAttribute::IceLite if session.ice_lite => {
+                Some(AttributeLine::Ice(IceParameter::Lite))
+            }
+            SessionAttribute::IcePacing => session
+                .ice_pacing
+                .map(|pacing| AttributeLine::Ice(IceParameter::Pacing(pacing))),
+            SessionAttribute::IceUfrag => session
+                .ice
+                .ufrag
+                .clone()
+                .map(|ufrag| AttributeLine::Ice(IceParameter::Ufrag(ufrag))),
+            SessionAttribute::IcePwd => session
+                .ice
+                .pwd
+                .clone()
+                .map(|pwd| AttributeLine::Ice(IceParameter::Pwd(pwd))),
+            SessionAttribute::IceOptions => session
+                .ice
+                .options
+                .clone()
+                .map(|options| AttributeLine::Ice(IceParameter::Options(options))),
+            SessionAttribute::Setup => session.setup_role.clone().map(AttributeLine::SetupRole),
+            SessionAttribute::DtlsConnection => {
+                session.dtls_connection.map(AttributeLine::DtlsConnection)
+            }
+            _ => None,
+        };
+        write_ln_option(f, &line)?;
+        Ok(line.is_some())
+    }
+}
+
 fn write_ln_option<W>(
     f: &mut Formatter<'_, W>,
     content: &Option<impl ufmt::uDisplay>,
@@ -68,6 +168,44 @@ where
     Ok(())
 }
 
+impl ufmt::uDisplay for SdpFrag<'_> {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        write_ln_option(f, &self.ice.ufrag.clone().map(IceParameter::Ufrag))?;
+        write_ln_option(f, &self.ice.pwd.clone().map(IceParameter::Pwd))?;
+        write_ln_option(f, &self.ice.options.clone().map(IceParameter::Options))?;
+        if self.end_of_candidates {
+            uwriteln!(f, "a=end-of-candidates")?;
+        }
+        for media in &self.media {
+            uwrite!(f, "{}", media)?;
+        }
+        Ok(())
+    }
+}
+
+impl ufmt::uDisplay for SdpFragMedia<'_> {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        uwriteln!(f, "{}", self.media)?;
+        write_ln_option(f, &self.mid.to_owned().map(Mid))?;
+        write_ln_option(f, &self.ice.ufrag.clone().map(IceParameter::Ufrag))?;
+        write_ln_option(f, &self.ice.pwd.clone().map(IceParameter::Pwd))?;
+        write_ln_option(f, &self.ice.options.clone().map(IceParameter::Options))?;
+        for candidate in &self.candidates {
+            uwriteln!(f, "{}", candidate)?;
+        }
+        if self.end_of_candidates {
+            uwriteln!(f, "a=end-of-candidates")?;
+        }
+        Ok(())
+    }
+}
+
 impl ufmt::uDisplay for MediaSection<'_> {
     fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
     where
@@ -88,8 +226,12 @@ impl ufmt::uDisplay for MediaSection<'_> {
 
         write_ln_option(f, &self.ice.options.clone().map(IceParameter::Options))?;
 
-        write_ln_option(f, &self.fingerprint)?;
+        for fingerprint in &self.fingerprints {
+            uwriteln!(f, "{}", fingerprint)?;
+        }
         write_ln_option(f, &self.setup_role)?;
+        write_ln_option(f, &self.dtls_connection)?;
+        write_ln_option(f, &self.tls_id)?;
         // uwriteln!(f, "{}", Mid(self.mid.clone()))?;
         write_ln_option(f, &self.mid.to_owned().map(Mid))?;
 
@@ -97,18 +239,29 @@ impl ufmt::uDisplay for MediaSection<'_> {
         for extmap in &self.extmap {
             uwriteln!(f, "{}", extmap)?;
         }
+        if self.extmap_allow_mixed {
+            uwriteln!(f, "a=extmap-allow-mixed")?;
+        }
+        if self.cryptex {
+            uwriteln!(f, "a=cryptex")?;
+        }
 
-        write_ln_option(f, &self.bundle_group)?;
+        for group in &self.groups {
+            uwriteln!(f, "{}", group)?;
+        }
         if self.bundle_only {
             uwriteln!(f, "a=bundle-only")?;
         }
         write_ln_option(f, &self.direction)?;
         write_ln_option(f, &self.msid_semantic)?;
-        write_ln_option(f, &self.msid)?;
+        for msid in &self.msids {
+            uwriteln!(f, "{}", msid)?;
+        }
         write_ln_option(f, &self.rtp)?;
         for rtcp_option in &self.rtcp_option {
             uwriteln!(f, "{}", rtcp_option)?;
         }
+        write_ln_option(f, &self.rtcp_xr)?;
 
         let known_payloads = self
             .payloads
@@ -116,6 +269,13 @@ impl ufmt::uDisplay for MediaSection<'_> {
             .filter_map(|p| p.parse::<u32>().ok())
             .collect::<Vec<_>>();
 
+        for rtcp_fb in self
+            .rtcp_fb
+            .iter()
+            .filter(|r| r.payload == FbPayload::Wildcard)
+        {
+            uwriteln!(f, "{}", rtcp_fb)?;
+        }
         for payload in &known_payloads {
             for rtp in self.rtp_map.iter().filter(|r| r.payload == *payload) {
                 uwriteln!(f, "{}", rtp)?;
@@ -135,11 +295,10 @@ impl ufmt::uDisplay for MediaSection<'_> {
             {
                 uwriteln!(f, "{}", rtp)?;
             }
-            for rtcp_fb in self
-                .rtcp_fb
-                .iter()
-                .filter(|r| !known_payloads.contains(&r.payload))
-            {
+            for rtcp_fb in self.rtcp_fb.iter().filter(|r| match r.payload {
+                FbPayload::Payload(payload) => !known_payloads.contains(&payload),
+                FbPayload::Wildcard => false,
+            }) {
                 uwriteln!(f, "{}", rtcp_fb)?;
             }
             for fmtp in self
@@ -151,7 +310,9 @@ impl ufmt::uDisplay for MediaSection<'_> {
             }
         }
 
-        write_ln_option(f, &self.ssrc_group)?;
+        for ssrc_group in &self.ssrc_groups {
+            uwriteln!(f, "{}", ssrc_group)?;
+        }
         for ssrc in &self.ssrc {
             uwriteln!(f, "{}", ssrc)?;
         }
@@ -221,7 +382,7 @@ impl ufmt::uDisplay for AttributeLine<'_> {
             AttributeLine::RtpMap(r)       => uwrite!(f, "{}", r),
             AttributeLine::PTime(p)        => uwrite!(f, "{}", p),
             AttributeLine::Ssrc(s)         => uwrite!(f, "{}", s),
-            AttributeLine::BundleGroup(b)  => uwrite!(f, "{}", b),
+            AttributeLine::Group(g)        => uwrite!(f, "{}", g),
             AttributeLine::SsrcGroup(s)    => uwrite!(f, "{}", s),
             AttributeLine::Fingerprint(fp) => uwrite!(f, "{}", fp),
             AttributeLine::Direction(d)    => uwrite!(f, "{}", d),
@@ -229,11 +390,16 @@ impl ufmt::uDisplay for AttributeLine<'_> {
             AttributeLine::Rtcp(r)         => uwrite!(f, "{}", r),
             AttributeLine::Fmtp(fmtp)      => uwrite!(f, "{}", fmtp),
             AttributeLine::RtcpFb(r)       => uwrite!(f, "{}", r),
+            AttributeLine::RtcpXr(r)       => uwrite!(f, "{}", r),
             AttributeLine::RtcpOption(r)   => uwrite!(f, "{}", r),
             AttributeLine::Control(c)      => uwrite!(f, "{}", c),
             AttributeLine::SetupRole(s)    => uwrite!(f, "{}", s),
+            AttributeLine::DtlsConnection(c) => uwrite!(f, "{}", c),
+            AttributeLine::TlsId(t)        => uwrite!(f, "{}", t),
             AttributeLine::Extmap(e)       => uwrite!(f, "{}", e),
             AttributeLine::BundleOnly      => uwrite!(f, "a=bundle-only"),
+            AttributeLine::ExtmapAllowMixed => uwrite!(f, "a=extmap-allow-mixed"),
+            AttributeLine::Cryptex => uwrite!(f, "a=cryptex"),
             AttributeLine::EoC             => uwrite!(f, "a=end-of-candidates"),
             AttributeLine::KeyValue {
                 key,
@@ -244,14 +410,14 @@ impl ufmt::uDisplay for AttributeLine<'_> {
     }
 }
 
-impl ufmt::uDisplay for BundleGroup<'_> {
+impl ufmt::uDisplay for Group<'_> {
     fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
     where
         W: uWrite + ?Sized,
     {
-        uwrite!(f, "a=group:BUNDLE")?;
-        for v in &self.0 {
-            uwrite!(f, " {}", v.as_ref())?;
+        uwrite!(f, "a=group:{}", self.semantics.as_str())?;
+        for mid in &self.mids {
+            uwrite!(f, " {}", mid.as_ref())?;
         }
         Ok(())
     }
@@ -338,29 +504,35 @@ impl ufmt::uDisplay for MsidSemantic<'_> {
     where
         W: uWrite + ?Sized,
     {
-        uwrite!(f, "a=msid-semantic: ")?;
-        uwrite!(f, "{} ", self.semantic.as_ref())?;
-        if let Some(ref token) = self.token {
-            uwrite!(f, "{}", token.as_ref())?;
+        uwrite!(f, "a=msid-semantic: {}", self.semantic.as_ref())?;
+        for token in &self.tokens {
+            uwrite!(f, " {}", token.as_ref())?;
         }
         Ok(())
     }
 }
-impl ufmt::uDisplay for Msid<'_> {
+/// `stream track` without the `a=msid:` prefix, shared with `a=ssrc`
+struct MsidValue<'m, 'a>(&'m Msid<'a>);
+impl ufmt::uDisplay for MsidValue<'_, '_> {
     fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
     where
         W: uWrite + ?Sized,
     {
-        uwrite!(f, "a=msid:")?;
-        for (i, x) in self.0.iter().enumerate() {
-            if i > 0 {
-                uwrite!(f, " ")?;
-            }
-            uwrite!(f, "{}", x.as_ref())?;
+        uwrite!(f, "{}", self.0.stream_id.as_deref().unwrap_or("-"))?;
+        if let Some(ref track_id) = self.0.track_id {
+            uwrite!(f, " {}", track_id.as_ref())?;
         }
         Ok(())
     }
 }
+impl ufmt::uDisplay for Msid<'_> {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        uwrite!(f, "a=msid:{}", MsidValue(self))
+    }
+}
 impl ufmt::uDisplay for Version {
     fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
     where
@@ -455,16 +627,12 @@ impl ufmt::uDisplay for Connection {
         }
     }
 }
-impl ufmt::uDisplay for SsrcGroup {
+impl ufmt::uDisplay for SsrcGroup<'_> {
     fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
     where
         W: uWrite + ?Sized,
     {
-        uwrite!(f, "a=ssrc-group:")?;
-        match self.semantic {
-            SsrcSemantic::FID => uwrite!(f, "FID")?,
-            SsrcSemantic::FEC => uwrite!(f, "FEC")?,
-        }
+        uwrite!(f, "a=ssrc-group:{}", self.semantic.as_str())?;
         for id in &self.ids {
             uwrite!(f, " {}", id)?;
         }
@@ -476,13 +644,32 @@ impl ufmt::uDisplay for Ssrc<'_> {
     where
         W: uWrite + ?Sized,
     {
-        uwrite!(
-            f,
-            "a=ssrc:{} {}:{}",
-            self.id,
-            self.attribute.as_ref(),
-            self.value.as_ref()
-        )
+        uwrite!(f, "a=ssrc:{} {}", self.id, self.attribute)
+    }
+}
+impl ufmt::uDisplay for SsrcAttribute<'_> {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        uwrite!(f, "{}:", self.name())?;
+        match self {
+            SsrcAttribute::Cname(value)
+            | SsrcAttribute::MsLabel(value)
+            | SsrcAttribute::Label(value)
+            | SsrcAttribute::Other { value, .. } => uwrite!(f, "{}", value.as_ref()),
+            SsrcAttribute::Msid(msid) => uwrite!(f, "{}", MsidValue(msid)),
+            SsrcAttribute::PreviousSsrc(ids) => {
+                let mut ids = ids.iter();
+                if let Some(first) = ids.next() {
+                    uwrite!(f, "{}", first)?;
+                }
+                for id in ids {
+                    uwrite!(f, " {}", id)?;
+                }
+                Ok(())
+            }
+        }
     }
 }
 impl ufmt::uDisplay for RtpMap<'_> {
@@ -569,8 +756,12 @@ impl ufmt::uDisplay for FbVal<'_> {
     {
         match self {
             FbVal::Ack(p) => uwrite!(f, "ack {}", p),
-            FbVal::Nack(p) => uwrite!(f, "nack {}", p),
+            FbVal::Nack(Some(p)) => uwrite!(f, "nack {}", p),
+            FbVal::Nack(None) => uwrite!(f, "nack"),
             FbVal::TrrInt(p) => uwrite!(f, "trr-int {}", p),
+            FbVal::Ccm(p) => uwrite!(f, "ccm {}", p),
+            FbVal::GoogRemb => uwrite!(f, "goog-remb"),
+            FbVal::TransportCc => uwrite!(f, "transport-cc"),
             FbVal::RtcpFbId {
                 id,
                 param: Some(param),
@@ -580,12 +771,103 @@ impl ufmt::uDisplay for FbVal<'_> {
     }
 }
 
+impl ufmt::uDisplay for RtcpXr<'_> {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        uwrite!(f, "a=rtcp-xr")?;
+        for (i, format) in self.formats.iter().enumerate() {
+            uwrite!(f, "{}{}", if i == 0 { ":" } else { " " }, format)?;
+        }
+        Ok(())
+    }
+}
+
+impl ufmt::uDisplay for XrFormat<'_> {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        fn max_size<W: uWrite + ?Sized>(
+            f: &mut Formatter<'_, W>,
+            name: &str,
+            max_size: &Option<u32>,
+        ) -> Result<(), W::Error> {
+            uwrite!(f, "{}", name)?;
+            if let Some(max_size) = max_size {
+                uwrite!(f, "={}", max_size)?;
+            }
+            Ok(())
+        }
+
+        match self {
+            XrFormat::PktLossRle(size) => max_size(f, "pkt-loss-rle", size),
+            XrFormat::PktDupRle(size) => max_size(f, "pkt-dup-rle", size),
+            XrFormat::PktRcptTimes(size) => max_size(f, "pkt-rcpt-times", size),
+            XrFormat::RcvrRtt { mode, max_size } => {
+                let mode = match mode {
+                    RcvrRttMode::All => "all",
+                    RcvrRttMode::Sender => "sender",
+                };
+                uwrite!(f, "rcvr-rtt={}", mode)?;
+                if let Some(max_size) = max_size {
+                    uwrite!(f, ":{}", max_size)?;
+                }
+                Ok(())
+            }
+            XrFormat::StatSummary(flags) => {
+                uwrite!(f, "stat-summary")?;
+                for (i, flag) in flags.iter().enumerate() {
+                    let flag = match flag {
+                        StatFlag::Loss => "loss",
+                        StatFlag::Dup => "dup",
+                        StatFlag::Jitt => "jitt",
+                        StatFlag::Ttl => "TTL",
+                        StatFlag::Hl => "HL",
+                    };
+                    uwrite!(f, "{}{}", if i == 0 { "=" } else { "," }, flag)?;
+                }
+                Ok(())
+            }
+            XrFormat::VoipMetrics => uwrite!(f, "voip-metrics"),
+            XrFormat::Other(format) => uwrite!(f, "{}", format.as_ref()),
+        }
+    }
+}
+
+impl ufmt::uDisplay for FbCcmParam {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        match self {
+            FbCcmParam::Fir => uwrite!(f, "fir"),
+            FbCcmParam::Tmmbr {
+                smaxpr: Some(smaxpr),
+            } => uwrite!(f, "tmmbr smaxpr={}", smaxpr),
+            FbCcmParam::Tmmbr { smaxpr: None } => uwrite!(f, "tmmbr"),
+            FbCcmParam::Tstr => uwrite!(f, "tstr"),
+            FbCcmParam::Vbcm(sub_message_types) => {
+                uwrite!(f, "vbcm")?;
+                for sub_message_type in sub_message_types {
+                    uwrite!(f, " {}", sub_message_type)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
 impl ufmt::uDisplay for Fb<'_> {
     fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
     where
         W: uWrite + ?Sized,
     {
-        uwrite!(f, "a=rtcp-fb:{} {}", self.payload, self.val)
+        match self.payload {
+            FbPayload::Wildcard => uwrite!(f, "a=rtcp-fb:* {}", self.val),
+            FbPayload::Payload(payload) => uwrite!(f, "a=rtcp-fb:{} {}", payload, self.val),
+        }
     }
 }
 impl ufmt::uDisplay for NetType {
@@ -597,19 +879,22 @@ impl ufmt::uDisplay for NetType {
     }
 }
 
-impl ufmt::uDisplay for Rtcp {
+impl ufmt::uDisplay for Rtcp<'_> {
     fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
     where
         W: uWrite + ?Sized,
     {
-        uwrite!(
-            f,
-            "a=rtcp:{} {} {} {}",
-            self.port,
-            self.net_type,
-            self.ip_ver,
-            IpAddress(&self.addr),
-        )
+        uwrite!(f, "a=rtcp:{}", self.port)?;
+        if let Some(address) = &self.address {
+            uwrite!(
+                f,
+                " {} {} {}",
+                address.net_type,
+                address.ip_ver,
+                address.addr
+            )?;
+        }
+        Ok(())
     }
 }
 
@@ -621,10 +906,45 @@ impl ufmt::uDisplay for IceParameter<'_> {
         match self {
             IceParameter::Ufrag(ufrag) => uwrite!(f, "a=ice-ufrag:{}", ufrag.as_ref()),
             IceParameter::Pwd(pwd) => uwrite!(f, "a=ice-pwd:{}", pwd.as_ref()),
-            IceParameter::Options(options) => uwrite!(f, "a=ice-options:{}", options.as_ref()),
+            IceParameter::Options(options) => uwrite!(f, "a=ice-options:{}", options),
             IceParameter::Mismatch => uwrite!(f, "a=ice-mismatch"),
             IceParameter::Lite => uwrite!(f, "a=ice-lite"),
+            IceParameter::RemoteCandidates(candidates) => {
+                uwrite!(f, "a=remote-candidates:")?;
+                for (i, candidate) in candidates.iter().enumerate() {
+                    if i > 0 {
+                        uwrite!(f, " ")?;
+                    }
+                    uwrite!(f, "{}", candidate)?;
+                }
+                Ok(())
+            }
+            IceParameter::Pacing(pacing) => uwrite!(f, "a=ice-pacing:{}", pacing),
+        }
+    }
+}
+
+impl ufmt::uDisplay for IceOptions<'_> {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        for (i, option) in self.0.iter().enumerate() {
+            if i > 0 {
+                uwrite!(f, " ")?;
+            }
+            uwrite!(f, "{}", option.as_str())?;
         }
+        Ok(())
+    }
+}
+
+impl ufmt::uDisplay for RemoteCandidate<'_> {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        uwrite!(f, "{} {} {}", self.component, self.addr, self.port)
     }
 }
 
@@ -643,10 +963,14 @@ impl ufmt::uDisplay for Extmap<'_> {
                 Direction::Inactive => uwrite!(f, "inactive")?,
             }
 
-            uwrite!(f, " {}", self.uri.as_ref())?;
+            uwrite!(f, " ")?;
         } else {
-            uwrite!(f, "a=extmap:{} {}", self.value, self.uri.as_ref())?;
+            uwrite!(f, "a=extmap:{} ", self.value)?;
+        }
+        if self.encrypted {
+            uwrite!(f, "{} ", ENCRYPT_URI)?;
         }
+        uwrite!(f, "{}", self.uri.as_ref())?;
         for a in &self.attributes {
             uwrite!(f, " {}", a.as_ref())?;
         }
@@ -755,6 +1079,31 @@ impl ufmt::uDisplay for CandidateType {
     }
 }
 
+impl ufmt::uDisplay for CandidateAddress<'_> {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        match self {
+            CandidateAddress::Ip(addr) => uwrite!(f, "{}", IpAddress(addr)),
+            CandidateAddress::Fqdn(name) => uwrite!(f, "{}", name.as_ref()),
+        }
+    }
+}
+
+impl ufmt::uDisplay for TcpType {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        match self {
+            TcpType::Active => uwrite!(f, "active"),
+            TcpType::Passive => uwrite!(f, "passive"),
+            TcpType::So => uwrite!(f, "so"),
+        }
+    }
+}
+
 impl ufmt::uDisplay for Candidate<'_> {
     fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
     where
@@ -763,29 +1112,43 @@ impl ufmt::uDisplay for Candidate<'_> {
         uwrite!(
             f,
             "a=candidate:{} {} {} {} {} {} typ {}",
-            self.foundation,
+            self.foundation.as_ref(),
             self.component,
             self.protocol,
             self.priority,
-            IpAddress(&self.addr),
+            self.addr,
             self.port,
             self.r#type,
         )?;
-        if let Some(x) = self.raddr {
-            uwrite!(f, " raddr {}", IpAddress(&x))?;
+        if let Some(x) = self.raddr.as_ref() {
+            uwrite!(f, " raddr {}", x)?;
         }
         if let Some(x) = self.rport {
             uwrite!(f, " rport {}", x)?;
         }
-        if let Some(x) = self.tcptype.as_ref() {
-            uwrite!(f, " tcptype {}", x.as_ref())?;
+        if let Some(x) = self.tcptype {
+            uwrite!(f, " tcptype {}", x)?;
         }
         if let Some(x) = self.generation {
             uwrite!(f, " generation {}", x)?;
         }
+        if let Some(x) = self.ufrag.as_ref() {
+            uwrite!(f, " ufrag {}", x.as_ref())?;
+        }
         if let Some(x) = self.network_id {
             uwrite!(f, " network-id {}", x)?;
         }
+        if let Some(x) = self.network_cost {
+            uwrite!(f, " network-cost {}", x)?;
+        }
+        for extension in &self.extensions {
+            uwrite!(
+                f,
+                " {} {}",
+                extension.name.as_ref(),
+                extension.value.as_ref()
+            )?;
+        }
         Ok(())
     }
 }
@@ -799,6 +1162,28 @@ impl ufmt::uDisplay for SetupRole {
             SetupRole::Active => uwrite!(f, "a=setup:active"),
             SetupRole::Passive => uwrite!(f, "a=setup:passive"),
             SetupRole::ActPass => uwrite!(f, "a=setup:actpass"),
+            SetupRole::HoldConn => uwrite!(f, "a=setup:holdconn"),
         }
     }
 }
+
+impl ufmt::uDisplay for ConnectionMode {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        match self {
+            ConnectionMode::New => uwrite!(f, "a=connection:new"),
+            ConnectionMode::Existing => uwrite!(f, "a=connection:existing"),
+        }
+    }
+}
+
+impl ufmt::uDisplay for TlsId<'_> {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        uwrite!(f, "a=tls-id:{}", self.0.as_ref())
+    }
+}
@@ -5,7 +5,7 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use sdp_nom::{sdp_lines, sdp_lines_all, Session};
+use sdp_nom::{attributes::rtcp::FbPayload, sdp_lines, sdp_lines_all, Session};
 
 fn with_all_fixtures<F>(
     sub_folders: &[impl AsRef<Path>],
@@ -53,6 +53,9 @@ fn parse_fixtures_order_preserving() {
 fn sort_certain_lines(mut session: Session) -> Session {
     session.media.iter_mut().for_each(|media| {
         media.fmtp.sort_by_key(|a| a.payload);
+        media
+            .rtcp_fb
+            .sort_by_key(|fb| fb.payload != FbPayload::Wildcard);
     });
     session
 }
@@ -122,3 +125,23 @@ fn parse_fixtures_sdp_transform_lazy() {
     })
     .unwrap();
 }
+
+#[test]
+fn fixture_candidates_have_plausible_priorities() {
+    with_all_fixtures(
+        &["order_preserving", "reparsable", "mozilla", "sdp_transform"],
+        |path| {
+            let fixture = std::fs::read_to_string(path).unwrap();
+            let session = Session::read_str(&fixture);
+            for candidate in session.media.iter().flat_map(|m| &m.candidates) {
+                assert!(
+                    candidate.has_plausible_priority(),
+                    "{:?}: {}",
+                    path.display(),
+                    candidate.priority
+                );
+            }
+        },
+    )
+    .unwrap();
+}